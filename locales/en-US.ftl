@@ -0,0 +1,514 @@
+## Commands
+
+prata = chat
+    .description = Start chatting with a character
+    .namn = name
+    .namn-description = The character's name
+fortsatt = continue
+    .description = Continue the latest reply in the channel
+grenar = branches
+    .description = Show the chat's branches or resume from one of them
+    .gren = branch
+    .gren-description = The branch to resume from
+grupp = group
+    .description = Group chats with several characters
+grupp-starta = start
+    .description = Start a group chat
+    .gubbe = character
+    .gubbe-description = The character who greets first
+    .gubbe2 = character2
+    .gubbe2-description = Another character
+    .gubbe3 = character3
+    .gubbe3-description = Another character
+    .gubbe4 = character4
+    .gubbe4-description = Another character
+    .ordning = order
+    .ordning-description = How the next speaker is chosen
+    .ordning-turordning = Round robin
+    .ordning-omnamnanden-namn = Mentions (@Name)
+    .ordning-naturlig-ordning = Natural order
+grupp-lagg-till = add
+    .description = Add a character to the chat
+    .gubbe = character
+    .gubbe-description = The character's name
+grupp-ta-bort = remove
+    .description = Remove a character from the chat
+    .gubbe = character
+    .gubbe-description = The character's name
+grupp-tysta = mute
+    .description = Mute a character, or let it speak again
+    .gubbe = character
+    .gubbe-description = The character's name
+grupp-ordning = order
+    .description = Choose how the next speaker is picked
+    .ordning = order
+    .ordning-description = How the next speaker is chosen
+    .ordning-turordning = Round robin
+    .ordning-omnamnanden-namn = Mentions (@Name)
+    .ordning-naturlig-ordning = Natural order
+gubbar = characters
+    .description = Browse the characters
+gubbe = character
+    .description = Create and manage characters
+gubbe-visa = show
+    .description = Show a character
+    .namn = name
+    .namn-description = The character's name
+gubbe-skapa = create
+    .description = Create a character
+    .namn = name
+    .namn-description = The character's name
+    .halsning = greeting
+    .halsning-description = The character's greeting
+    .beskrivning = description
+    .beskrivning-description = The character's description
+    .emoji = emoji
+    .emoji-description = The character's emoji
+    .profilbild = avatar
+    .profilbild-description = The character's avatar (URL)
+gubbe-andra = edit
+    .description = Edit a character
+    .namn = name
+    .namn-description = The character's name
+    .halsning = greeting
+    .halsning-description = The character's greeting
+    .beskrivning = description
+    .beskrivning-description = The character's description
+    .emoji = emoji
+    .emoji-description = The character's emoji
+    .profilbild = avatar
+    .profilbild-description = The character's avatar (URL)
+    .mall = preset
+    .mall-description = The character's prompt preset, or "none" for the channel's preset
+gubbe-exempel = examples
+    .description = Edit a character's example dialogue
+    .namn = name
+    .namn-description = The character's name
+gubbe-doda = kill
+    .description = Kill a character
+    .namn = name
+    .namn-description = The character's name
+gubbe-overlat = transfer
+    .description = Give a character to someone else
+    .namn = name
+    .namn-description = The character's name
+    .agare = owner
+    .agare-description = The character's new owner
+gubbe-publicera = publish
+    .description = Share a character with every server
+    .namn = name
+    .namn-description = The character's name
+gubbe-kopiera = copy
+    .description = Copy a public character to the server
+    .namn = name
+    .namn-description = The public character's name
+gubbe-importera = import
+    .description = Import a SillyTavern card
+    .kort = card
+    .kort-description = The character card (PNG or JSON)
+gubbe-exportera = export
+    .description = Export a character as a SillyTavern card
+    .namn = name
+    .namn-description = The character's name
+    .format = format
+    .format-description = File format (PNG by default)
+lorebok = lorebook
+    .description = Lorebooks with entries triggered by keywords
+lorebok-skapa = create
+    .description = Add an entry to a lorebook
+    .bok = book
+    .bok-description = The lorebook's name
+    .nycklar = keys
+    .nycklar-description = Keywords, separated by commas
+    .innehall = content
+    .innehall-description = The entry's content
+    .sekundara-nycklar = secondary_keys
+    .sekundara-nycklar-description = Secondary keywords, separated by commas
+    .position = position
+    .position-description = Where the entry is placed
+    .position-fore-beskrivningen = Before the description
+    .position-efter-beskrivningen = After the description
+    .position-fore-senaste-meddelandet = Before the latest message
+    .prioritet = priority
+    .prioritet-description = Priority (higher priority is placed first)
+lorebok-lista = list
+    .description = Show a lorebook's entries
+    .bok = book
+    .bok-description = The lorebook's name
+lorebok-andra = edit
+    .description = Edit an entry
+    .bok = book
+    .bok-description = The lorebook's name
+    .nummer = number
+    .nummer-description = The entry's number
+    .nycklar = keys
+    .nycklar-description = Keywords, separated by commas
+    .innehall = content
+    .innehall-description = The entry's content
+    .sekundara-nycklar = secondary_keys
+    .sekundara-nycklar-description = Secondary keywords, separated by commas
+    .position = position
+    .position-description = Where the entry is placed
+    .position-fore-beskrivningen = Before the description
+    .position-efter-beskrivningen = After the description
+    .position-fore-senaste-meddelandet = Before the latest message
+    .prioritet = priority
+    .prioritet-description = Priority (higher priority is placed first)
+    .paslaget = enabled
+    .paslaget-description = Whether the entry is enabled
+lorebok-radera = delete
+    .description = Delete an entry
+    .bok = book
+    .bok-description = The lorebook's name
+    .nummer = number
+    .nummer-description = The entry's number
+lorebok-slang = throw-away
+    .description = Throw away a whole lorebook
+    .bok = book
+    .bok-description = The lorebook's name
+lorebok-importera = import
+    .description = Import SillyTavern World Info
+    .fil = file
+    .fil-description = SillyTavern World Info (JSON)
+    .namn = name
+    .namn-description = The lorebook's name
+lorebok-koppla = attach
+    .description = Attach a lorebook to a character or the channel
+    .bok = book
+    .bok-description = The lorebook's name
+    .gubbe = character
+    .gubbe-description = The character's name (otherwise the lorebook is attached to the channel)
+lorebok-lossa = detach
+    .description = Detach a lorebook from a character or the channel
+    .bok = book
+    .bok-description = The lorebook's name
+    .gubbe = character
+    .gubbe-description = The character's name (otherwise the lorebook is detached from the channel)
+makro = macro
+    .description = Custom macros for the channel
+makro-skapa = create
+    .description = Create or edit a macro
+    .namn = name
+    .namn-description = The macro's name, used as {"{{"}name{"}}"}
+    .text = text
+    .text-description = The text the macro is replaced with
+makro-lista = list
+    .description = Show the macros
+makro-radera = delete
+    .description = Delete a macro
+    .namn = name
+    .namn-description = The macro's name
+mall = preset
+    .description = Prompt presets
+mall-lista = list
+    .description = Show the prompt presets
+mall-kanal = channel
+    .description = Choose the channel's prompt preset
+    .namn = name
+    .namn-description = The prompt preset for this channel, empty for the default preset
+persona = persona
+    .description = Who you are in the roleplay
+persona-skapa = create
+    .description = Create or update a persona
+    .namn = name
+    .namn-description = The persona's name
+    .beskrivning = description
+    .beskrivning-description = Who you are in the roleplay
+    .profilbild = avatar
+    .profilbild-description = Link to the persona's avatar
+persona-lista = list
+    .description = Show your personas
+persona-anvand = use
+    .description = Use a persona in this channel
+    .namn = name
+    .namn-description = The persona's name
+persona-sluta = stop
+    .description = Stop using a persona in this channel
+persona-radera = delete
+    .description = Delete a persona
+    .namn = name
+    .namn-description = The persona's name
+sammanfatta = summary
+    .description = Show or edit the chat's summary
+    .sammanfattning = summary
+    .sammanfattning-description = The new summary
+sampler = sampler
+    .description = Sampler settings for characters and chats
+sampler-visa = show
+    .description = Show the sampler settings
+    .gubbe = character
+    .gubbe-description = The character to show, otherwise the chat in this channel
+sampler-andra = edit
+    .description = Edit the sampler settings
+    .gubbe = character
+    .gubbe-description = The character to edit, otherwise the chat in this channel
+    .forinstallning = preset
+    .forinstallning-description = The preset to start from
+    .temperatur = temperature
+    .temperatur-description = Temperature
+    .top-p = top_p
+    .top-p-description = Top P
+    .top-k = top_k
+    .top-k-description = Top K
+    .min-p = min_p
+    .min-p-description = Min P
+    .upprepningsstraff = repetition_penalty
+    .upprepningsstraff-description = Repetition penalty
+    .frekvensstraff = frequency_penalty
+    .frekvensstraff-description = Frequency penalty
+    .narvarostraff = presence_penalty
+    .narvarostraff-description = Presence penalty
+    .max-tokens = max_tokens
+    .max-tokens-description = Maximum number of tokens per reply
+    .stopp = stop
+    .stopp-description = Stop sequences, separated by |
+    .fro = seed
+    .fro-description = Seed for repeatable replies
+    .logit-bias = logit_bias
+    .logit-bias-description = Logit bias, e.g. token:strength
+sampler-aterstall = reset
+    .description = Reset the sampler settings
+    .gubbe = character
+    .gubbe-description = The character to reset, otherwise the chat in this channel
+    .installning = setting
+    .installning-description = The setting to reset, otherwise all of them
+    .installning-forinstallning = Preset
+    .installning-temperatur = Temperature
+    .installning-top-p = Top P
+    .installning-top-k = Top K
+    .installning-min-p = Min P
+    .installning-upprepningsstraff = Repetition penalty
+    .installning-frekvensstraff = Frequency penalty
+    .installning-narvarostraff = Presence penalty
+    .installning-hogsta-antal-tokens = Max tokens
+    .installning-stoppsekvenser = Stop sequences
+    .installning-fro = Seed
+    .installning-logit-bias = Logit bias
+sampler-forinstallningar = presets
+    .description = Show the presets
+sprak = language
+    .description = Choose the server's language
+    .sprak = language
+    .sprak-description = The server's language, empty to follow each user's language
+
+## Shared
+
+character-not-found = Character not found!
+named-character-not-found = The character { $name } was not found!
+no-chat-in-channel = No chat was found in this channel!
+message-not-found = Message not found!
+not-allowed = You may not change { $character }! Only the character's owner, the server's administrators and the bot's administrators may.
+greeting = Greeting
+owner = Owner
+command-error = Error in command `{ $command }`: { $error }
+generation-failed = Something went wrong, don't blame me: { $error }
+empty-completion = Something has gone wrong here!
+click-the-button = Please click the button below!
+
+## Chats
+
+reply-continued = Hooray! The reply was continued.
+no-reply-to-continue = The chat has no reply to continue!
+branch-line = **{ $number }.** { $character }, { $turns } messages{ $latest ->
+        [true] {" "}(latest)
+       *[false] {""}
+    }: { $preview } { $link }
+branch-list =
+    The chat has { $count } branches:
+    { $list }
+    Reply to a branch's message, or pick a branch with `/branches branch:`.
+branch-not-found = Branch not found!
+branch-has-no-reply = The branch has no reply to continue from!
+branch-message-not-found = The branch's message was not found!
+branch-resumed = Hooray! The chat continues from branch { $branch }.
+summary-changed = Hooray! The summary was changed.
+no-summary = The chat has no summary yet!
+summary-footer = summarising { $turns } messages
+message-too-long-to-edit = The message is too long to be edited here!
+message-being-edited = The message is being edited…
+message-being-edited-by = The message is being edited by { $user }.
+reply-footer = { $label } | took { $seconds }s | { $characters } characters | { $tokens }/{ $context } tokens{ $finish ->
+        [stop] {" "}| done
+        [length] {" "}| cut off, ⏩ continues
+        [cancelled] {" "}| stopped
+       *[other] {""}
+    }
+edited-footer = { $footer } (edited)
+message-busy = Someone else is already busy with the message, hold on!
+stop-not-allowed = Only whoever asked for the reply and administrators may stop it!
+edit-message-modal = Edit the message
+edit-message-content = Content
+edit-message-content-placeholder = The message's content…
+edit-message-continuation = Continuation
+edit-message-continuation-placeholder = The rest of a long message…
+
+## Group chats
+
+group-needs-two = A group chat needs at least two different characters!
+group-footer = group chat with { $names }
+group-already-member = { $character } is already in the chat!
+group-joined = Hooray! { $character } joined the chat.
+group-cannot-remove = { $character } could not be removed! The character has to be in a group chat, and the last character cannot be removed.
+group-left = Hooray! { $character } left the chat.
+group-not-member = { $character } is not in any group chat!
+group-muted = Hooray! { $character } was muted.
+group-unmuted = Hooray! { $character } may speak again.
+not-a-group-chat = The chat is not a group chat!
+speaker-strategy-changed = Hooray! The speaking order is now { $strategy }.
+speaker-strategy-round-robin = round robin
+speaker-strategy-mention = mentions (@Name)
+speaker-strategy-natural = natural order
+
+## Characters
+
+no-characters = You don't have any characters, it seems! Or none were found :(
+character-name-missing = Hey, I think you forgot a name there!
+character-name-invalid = A character's name can't contain `/`!
+character-created = Hooray! The character { $character } was created.
+character-changed = Hooray! The character { $character } was changed.
+character-killed = Hooray! The character { $character } was killed.
+character-belongs-to-someone-else = The character { $character } already exists and belongs to someone else!
+bot-cannot-own = Hey, a bot cannot own a character!
+character-transferred = Hooray! The character { $character } is now owned by { $owner }.
+character-already-public = The character { $character } is already public!
+public-character-exists = There is already a public character called { $name }!
+character-published = Hooray! The character { $character } is now public and available in every server.
+copy-needs-server = Characters can only be copied to a server!
+public-character-not-found = No public character was found!
+server-character-exists = The server already has a character called { $name }!
+character-copied = Hooray! The character { $character } was copied to the server.
+card-unreadable = The card could not be read: { $error }
+card-too-large = The card is larger than { $megabytes } MB!
+card-name-missing = Hey, the card is missing a name!
+card-personality = Personality
+card-scenario = Scenario
+imported-character-exists = The server already has a character called { $name }! Delete it or rename the card first.
+character-imported = Hooray! The character { $character } was imported.{ $unmapped_count ->
+        [0] {""}
+       *[other] {" "}The following fields could not be carried over: { $unmapped }
+    }
+character-exported = Hooray! The character { $character } was exported.
+no-preset-keyword = none
+create-character-modal = Create a character
+create-character-name = Name
+create-character-name-placeholder = The character's name…
+create-character-greeting = Greeting
+create-character-greeting-placeholder = The character's greeting…
+create-character-description = Description
+create-character-description-placeholder = The character's description…
+create-character-emoji = Emoji
+create-character-emoji-placeholder = The character's emoji…
+create-character-avatar = Avatar
+create-character-avatar-placeholder = The character's avatar…
+example-messages-modal = Example dialogue
+example-messages-label = Examples
+example-messages-placeholder = <START> followed by lines starting with {"{{"}user{"}}"}: and {"{{"}char{"}}"}:
+examples-too-long = The examples for { $character } are too long to edit here!
+examples-changed = Hooray! { $blocks ->
+        [0] The character { $character } no longer has any examples.
+        [one] The character { $character } now has one example.
+       *[other] The character { $character } now has { $blocks } examples.
+    }
+
+## Lorebooks
+
+lorebook-not-found = Lorebook not found!
+lorebook-empty = The lorebook is empty!
+lore-entry-not-found = Entry not found!
+lore-entry-added = Hooray! Entry { $entry } was added to the lorebook { $lorebook }.
+lore-entry-line = **{ $number }.** { $keys }{ $enabled ->
+        [false] {" "}(disabled)
+       *[true] {""}
+    }
+    { $preview }
+lore-entry-changed = Hooray! Entry { $entry } in the lorebook { $lorebook } was changed.
+lore-entry-deleted = Hooray! Entry { $entry } in the lorebook { $lorebook } was deleted.
+lorebook-deleted = Hooray! The lorebook { $lorebook } was thrown away.
+file-unreadable = The file could not be read: { $error }
+lorebook-imported = Hooray! The lorebook { $lorebook } was imported with { $entries } entries.
+imported-lorebook-exists = The server already has a lorebook called { $lorebook }! Throw it away or pick another name first.
+lorebook-not-allowed = You may not change the lorebook { $lorebook }! Only the lorebook's owner, the server's administrators and the bot's administrators may.
+lorebook-attached = Hooray! The lorebook { $lorebook } was attached to { $character }.
+lorebook-attached-to-channel = Hooray! The lorebook { $lorebook } was attached to the channel.
+lorebook-detached = Hooray! The lorebook { $lorebook } was detached from { $character }.
+lorebook-detached-from-channel = Hooray! The lorebook { $lorebook } was detached from the channel.
+
+## Personas
+
+persona-created = Hooray! The persona { $name } was created. Choose it in a channel with `/persona use`.
+persona-updated = Hooray! The persona { $name } was updated.
+no-personas = You don't have any personas! Create one with `/persona create`.
+persona-line = **{ $name }**{ $active ->
+        [true] {" "}(used here)
+       *[false] {""}
+    }
+    { $description }
+personas-title = Your personas
+persona-not-found = The persona { $name } was not found!
+persona-activated = Hooray! You are now { $name } in this channel.
+no-active-persona = You aren't using a persona in this channel!
+persona-deactivated = Hooray! You are no longer { $name } in this channel.
+persona-deleted = Hooray! The persona { $name } was deleted.
+
+## Sampler
+
+sampler-title-character = Sampler settings for { $character }
+sampler-title-chat = Sampler settings for the chat
+sampler-overrides = Own settings
+sampler-effective = Used when generating
+sampler-preset-not-found = The preset { $name } was not found!
+invalid-logit-bias = Invalid logit bias! Write it as `token:strength, token:strength`.
+sampler-changed-character =
+    Hooray! The sampler settings for { $character } were changed.
+    { $settings }
+sampler-changed-chat =
+    Hooray! The sampler settings for the chat were changed.
+    { $settings }
+sampler-reset-character = Hooray! { $character } now uses the default settings.
+sampler-reset-chat = Hooray! The chat now uses the character's settings.
+sampler-cleared-character = Hooray! { $character } no longer sets { $setting }.
+sampler-cleared-chat = Hooray! The chat no longer sets { $setting }.
+no-sampler-presets = There are no presets!
+sampler-presets-title = Presets
+sampler-no-settings = no settings
+sampler-setting = { $setting ->
+        [preset] preset
+       *[other] { $setting }
+    }
+
+## Macros
+
+macro-name-invalid = A macro can't be called { $name }! The name may only contain letters, digits, - and _, and can't be a built-in macro.
+macro-created = Hooray! The macro {"{{"}{ $name }{"}}"} was created.
+macro-updated = Hooray! The macro {"{{"}{ $name }{"}}"} was changed.
+no-macros = There are no macros! Create one with `/macro create`.
+macros-title = Macros
+macro-line = **{"{{"}{ $name }{"}}"}**{ $channel ->
+        [true] {" "}(this channel)
+       *[false] {""}
+    }
+    { $text }
+macro-not-found = The macro { $name } was not found!
+macro-deleted = Hooray! The macro {"{{"}{ $name }{"}}"} was deleted.
+macros-not-allowed = Only the server's administrators and the bot's administrators may change the channel's macros!
+
+## Prompt presets
+
+prompt-presets-title = Prompt presets
+prompt-preset-line = **{ $name }**{ $default ->
+        [true] {" "}(default)
+       *[false] {""}
+    }{ $channel ->
+        [true] {" "}(this channel)
+       *[false] {""}
+    }
+prompt-preset-not-found = The prompt preset { $name } was not found!
+channel-prompt-preset-reset = Hooray! The channel now uses the default preset.
+channel-prompt-preset-changed = Hooray! The channel now uses the prompt preset { $name }.
+channel-prompt-preset-not-allowed = Only the server's administrators and the bot's administrators may change the channel's prompt preset!
+
+## Languages
+
+language-not-allowed = Only the server's administrators and the bot's administrators may change the server's language!
+language-changed = Hooray! The server now speaks English.
+language-reset = Hooray! The bot now replies in each user's own language.
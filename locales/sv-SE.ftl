@@ -0,0 +1,332 @@
+## Kommandon
+## Namn och beskrivningar av parametrar finns i koden; här står bara
+## beskrivningarna av själva kommandona.
+
+prata =
+    .description = Börja prata med en gubbe
+fortsatt =
+    .description = Fortsätt det senaste svaret i kanalen
+grenar =
+    .description = Visa chattens grenar eller fortsätt från en av dem
+grupp =
+    .description = Gruppchatter med flera gubbar
+grupp-starta =
+    .description = Starta en gruppchatt
+grupp-lagg-till =
+    .description = Lägg till en gubbe i chatten
+grupp-ta-bort =
+    .description = Ta bort en gubbe från chatten
+grupp-tysta =
+    .description = Tysta en gubbe, eller låt den prata igen
+grupp-ordning =
+    .description = Välj hur nästa talare väljs
+gubbar =
+    .description = Bläddra bland gubbarna
+gubbe =
+    .description = Skapa och hantera gubbar
+gubbe-visa =
+    .description = Visa en gubbe
+gubbe-skapa =
+    .description = Skapa en gubbe
+gubbe-andra =
+    .description = Ändra en gubbe
+gubbe-exempel =
+    .description = Ändra en gubbes exempeldialog
+gubbe-doda =
+    .description = Döda en gubbe
+gubbe-overlat =
+    .description = Ge en gubbe till någon annan
+gubbe-publicera =
+    .description = Dela en gubbe med alla servrar
+gubbe-kopiera =
+    .description = Kopiera en offentlig gubbe till servern
+gubbe-importera =
+    .description = Importera ett SillyTavern-kort
+gubbe-exportera =
+    .description = Exportera en gubbe som ett SillyTavern-kort
+lorebok =
+    .description = Loreböcker med inlägg som utlöses av nyckelord
+lorebok-skapa =
+    .description = Lägg till ett inlägg i en lorebok
+lorebok-lista =
+    .description = Visa en loreboks inlägg
+lorebok-andra =
+    .description = Ändra ett inlägg
+lorebok-radera =
+    .description = Radera ett inlägg
+lorebok-slang =
+    .description = Släng en hel lorebok
+lorebok-importera =
+    .description = Importera SillyTavern World Info
+lorebok-koppla =
+    .description = Koppla en lorebok till en gubbe eller kanalen
+lorebok-lossa =
+    .description = Lossa en lorebok från en gubbe eller kanalen
+makro =
+    .description = Egna makron för kanalen
+makro-skapa =
+    .description = Skapa eller ändra ett makro
+makro-lista =
+    .description = Visa makrona
+makro-radera =
+    .description = Radera ett makro
+mall =
+    .description = Promptmallar
+mall-lista =
+    .description = Visa promptmallarna
+mall-kanal =
+    .description = Välj kanalens promptmall
+persona =
+    .description = Vem du är i rollspelet
+persona-skapa =
+    .description = Skapa eller uppdatera en persona
+persona-lista =
+    .description = Visa dina personor
+persona-anvand =
+    .description = Använd en persona i den här kanalen
+persona-sluta =
+    .description = Sluta använda en persona i den här kanalen
+persona-radera =
+    .description = Radera en persona
+sammanfatta =
+    .description = Visa eller ändra chattens sammanfattning
+sampler =
+    .description = Samplerinställningar för gubbar och chatter
+sampler-visa =
+    .description = Visa samplerinställningarna
+sampler-andra =
+    .description = Ändra samplerinställningarna
+sampler-aterstall =
+    .description = Återställ samplerinställningarna
+sampler-forinstallningar =
+    .description = Visa förinställningarna
+sprak =
+    .description = Välj serverns språk
+
+## Gemensamt
+
+character-not-found = Gubben hittades inte!
+named-character-not-found = Gubben { $name } hittades inte!
+no-chat-in-channel = Ingen chatt hittades i den här kanalen!
+message-not-found = Meddelandet hittades inte!
+not-allowed = Du får inte ändra { $character }! Bara gubbens ägare, serverns administratörer och botens administratörer får det.
+greeting = Hälsning
+owner = Ägare
+command-error = Fel i kommandot `{ $command }`: { $error }
+generation-failed = Någonting gick fel, skyll inte på mig: { $error }
+empty-completion = Någonting har gått fel här!
+click-the-button = Var snäll och klicka på nedanstående knapp!
+
+## Chattar
+
+reply-continued = Hurra! Svaret fortsattes.
+no-reply-to-continue = Chatten har inget svar att fortsätta!
+branch-line = **{ $number }.** { $character }, { $turns } meddelanden{ $latest ->
+        [true] {" "}(senaste)
+       *[false] {""}
+    }: { $preview } { $link }
+branch-list =
+    Chatten har { $count } grenar:
+    { $list }
+    Svara på en grens meddelande, eller välj en gren med `/grenar gren:`.
+branch-not-found = Grenen hittades inte!
+branch-has-no-reply = Grenen har inget svar att fortsätta från!
+branch-message-not-found = Grenens meddelande hittades inte!
+branch-resumed = Hurra! Chatten fortsätter från gren { $branch }.
+summary-changed = Hurra! Sammanfattningen ändrades.
+no-summary = Chatten har ingen sammanfattning än!
+summary-footer = sammanfattar { $turns } meddelanden
+message-too-long-to-edit = Meddelandet är för långt för att redigeras här!
+message-being-edited = Meddelandet redigeras…
+message-being-edited-by = Meddelandet håller på att redigeras av { $user }.
+reply-footer = { $label } | tog { $seconds }s | { $characters } tecken | { $tokens }/{ $context } tokens{ $finish ->
+        [stop] {" "}| klar
+        [length] {" "}| avklippt, ⏩ fortsätter
+        [cancelled] {" "}| avbruten
+       *[other] {""}
+    }
+edited-footer = { $footer } (redigerad)
+message-busy = Någon annan håller redan på med meddelandet, vänta lite!
+stop-not-allowed = Bara den som bad om svaret och administratörer får stoppa det!
+edit-message-modal = Redigera meddelandet
+edit-message-content = Innehåll
+edit-message-content-placeholder = Meddelandets innehåll…
+edit-message-continuation = Fortsättning
+edit-message-continuation-placeholder = Resten av ett långt meddelande…
+
+## Gruppchatter
+
+group-needs-two = En gruppchatt behöver minst två olika gubbar!
+group-footer = gruppchatt med { $names }
+group-already-member = { $character } är redan med i chatten!
+group-joined = Hurra! { $character } gick med i chatten.
+group-cannot-remove = { $character } kunde inte tas bort! Gubben måste vara med i en gruppchatt, och den sista gubben kan inte tas bort.
+group-left = Hurra! { $character } lämnade chatten.
+group-not-member = { $character } är inte med i någon gruppchatt!
+group-muted = Hurra! { $character } tystades.
+group-unmuted = Hurra! { $character } får prata igen.
+not-a-group-chat = Chatten är ingen gruppchatt!
+speaker-strategy-changed = Hurra! Talarordningen är nu { $strategy }.
+speaker-strategy-round-robin = turordning
+speaker-strategy-mention = omnämnanden (@Namn)
+speaker-strategy-natural = naturlig ordning
+
+## Gubbar
+
+no-characters = Du har inga gubbar, verkar det som! Eller så hittades inga :(
+character-name-missing = Hörrudu, jag tror att du glömde ett namn där!
+character-name-invalid = Gubbens namn får inte innehålla `/`!
+character-created = Hurra! Gubben { $character } skapades.
+character-changed = Hurra! Gubben { $character } ändrades.
+character-killed = Hurra! Gubben { $character } dödades.
+character-belongs-to-someone-else = Gubben { $character } finns redan och tillhör någon annan!
+bot-cannot-own = Hörrudu, en bot kan inte äga en gubbe!
+character-transferred = Hurra! Gubben { $character } ägs nu av { $owner }.
+character-already-public = Gubben { $character } är redan offentlig!
+public-character-exists = Det finns redan en offentlig gubbe som heter { $name }!
+character-published = Hurra! Gubben { $character } är nu offentlig och finns i alla servrar.
+copy-needs-server = Gubbar kan bara kopieras till en server!
+public-character-not-found = Ingen offentlig gubbe hittades!
+server-character-exists = Servern har redan en gubbe som heter { $name }!
+character-copied = Hurra! Gubben { $character } kopierades till servern.
+card-unreadable = Kortet kunde inte läsas: { $error }
+card-too-large = Kortet är större än { $megabytes } MB!
+card-name-missing = Hörrudu, kortet saknar ett namn!
+card-personality = Personlighet
+card-scenario = Scenario
+imported-character-exists = Servern har redan en gubbe som heter { $name }! Radera den eller byt namn på kortet först.
+character-imported = Hurra! Gubben { $character } importerades.{ $unmapped_count ->
+        [0] {""}
+       *[other] {" "}Följande fält kunde inte överföras: { $unmapped }
+    }
+character-exported = Hurra! Gubben { $character } exporterades.
+no-preset-keyword = ingen
+create-character-modal = Skapa en gubbe
+create-character-name = Namn
+create-character-name-placeholder = Gubbens namn…
+create-character-greeting = Hälsning
+create-character-greeting-placeholder = Gubbens hälsning…
+create-character-description = Beskrivning
+create-character-description-placeholder = Gubbens beskrivning…
+create-character-emoji = Emoji
+create-character-emoji-placeholder = Gubbens emoji…
+create-character-avatar = Profilbild
+create-character-avatar-placeholder = Gubbens profilbild…
+example-messages-modal = Exempeldialog
+example-messages-label = Exempel
+example-messages-placeholder = <START> följt av rader som börjar med {"{{"}user{"}}"}: och {"{{"}char{"}}"}:
+examples-too-long = Exemplen för { $character } är för långa för att ändras här!
+examples-changed = Hurra! { $blocks ->
+        [0] Gubben { $character } har inga exempel längre.
+        [one] Gubben { $character } har nu ett exempel.
+       *[other] Gubben { $character } har nu { $blocks } exempel.
+    }
+
+## Loreböcker
+
+lorebook-not-found = Loreboken hittades inte!
+lorebook-empty = Loreboken är tom!
+lore-entry-not-found = Inlägget hittades inte!
+lore-entry-added = Hurra! Inlägg { $entry } lades till i loreboken { $lorebook }.
+lore-entry-line = **{ $number }.** { $keys }{ $enabled ->
+        [false] {" "}(avstängd)
+       *[true] {""}
+    }
+    { $preview }
+lore-entry-changed = Hurra! Inlägg { $entry } i loreboken { $lorebook } ändrades.
+lore-entry-deleted = Hurra! Inlägg { $entry } i loreboken { $lorebook } raderades.
+lorebook-deleted = Hurra! Loreboken { $lorebook } slängdes.
+file-unreadable = Filen kunde inte läsas: { $error }
+lorebook-imported = Hurra! Loreboken { $lorebook } importerades med { $entries } inlägg.
+imported-lorebook-exists = Servern har redan en lorebok som heter { $lorebook }! Släng den eller välj ett annat namn först.
+lorebook-not-allowed = Du får inte ändra loreboken { $lorebook }! Bara lorebokens ägare, serverns administratörer och botens administratörer får det.
+lorebook-attached = Hurra! Loreboken { $lorebook } kopplades till { $character }.
+lorebook-attached-to-channel = Hurra! Loreboken { $lorebook } kopplades till kanalen.
+lorebook-detached = Hurra! Loreboken { $lorebook } lossades från { $character }.
+lorebook-detached-from-channel = Hurra! Loreboken { $lorebook } lossades från kanalen.
+
+## Personor
+
+persona-created = Hurra! Personan { $name } skapades. Välj den i en kanal med `/persona använd`.
+persona-updated = Hurra! Personan { $name } uppdaterades.
+no-personas = Du har inga personor! Skapa en med `/persona skapa`.
+persona-line = **{ $name }**{ $active ->
+        [true] {" "}(används här)
+       *[false] {""}
+    }
+    { $description }
+personas-title = Dina personor
+persona-not-found = Personan { $name } hittades inte!
+persona-activated = Hurra! Du är nu { $name } i den här kanalen.
+no-active-persona = Du använder ingen persona i den här kanalen!
+persona-deactivated = Hurra! Du är inte längre { $name } i den här kanalen.
+persona-deleted = Hurra! Personan { $name } raderades.
+
+## Sampler
+
+sampler-title-character = Samplerinställningar för { $character }
+sampler-title-chat = Samplerinställningar för chatten
+sampler-overrides = Egna inställningar
+sampler-effective = Används vid generering
+sampler-preset-not-found = Förinställningen { $name } hittades inte!
+invalid-logit-bias = Ogiltig logit bias! Skriv den som `token:styrka, token:styrka`.
+sampler-changed-character =
+    Hurra! Samplerinställningarna för { $character } ändrades.
+    { $settings }
+sampler-changed-chat =
+    Hurra! Samplerinställningarna för chatten ändrades.
+    { $settings }
+sampler-reset-character = Hurra! { $character } använder nu standardinställningarna.
+sampler-reset-chat = Hurra! Chatten använder nu gubbens inställningar.
+sampler-cleared-character = Hurra! { $character } sätter inte längre { $setting }.
+sampler-cleared-chat = Hurra! Chatten sätter inte längre { $setting }.
+no-sampler-presets = Det finns inga förinställningar!
+sampler-presets-title = Förinställningar
+sampler-no-settings = inga inställningar
+sampler-setting = { $setting ->
+        [preset] förinställning
+        [temperature] temperatur
+        [repetition_penalty] upprepningsstraff
+        [frequency_penalty] frekvensstraff
+        [presence_penalty] närvarostraff
+        [stop] stopp
+        [seed] frö
+       *[other] { $setting }
+    }
+
+## Makron
+
+macro-name-invalid = Makrot kan inte heta { $name }! Namnet får bara innehålla bokstäver, siffror, - och _, och kan inte vara ett inbyggt makro.
+macro-created = Hurra! Makrot {"{{"}{ $name }{"}}"} skapades.
+macro-updated = Hurra! Makrot {"{{"}{ $name }{"}}"} ändrades.
+no-macros = Det finns inga makron! Skapa ett med `/makro skapa`.
+macros-title = Makron
+macro-line = **{"{{"}{ $name }{"}}"}**{ $channel ->
+        [true] {" "}(den här kanalen)
+       *[false] {""}
+    }
+    { $text }
+macro-not-found = Makrot { $name } hittades inte!
+macro-deleted = Hurra! Makrot {"{{"}{ $name }{"}}"} raderades.
+macros-not-allowed = Bara serverns administratörer och botens administratörer får ändra kanalens makron!
+
+## Promptmallar
+
+prompt-presets-title = Promptmallar
+prompt-preset-line = **{ $name }**{ $default ->
+        [true] {" "}(standard)
+       *[false] {""}
+    }{ $channel ->
+        [true] {" "}(den här kanalen)
+       *[false] {""}
+    }
+prompt-preset-not-found = Promptmallen { $name } hittades inte!
+channel-prompt-preset-reset = Hurra! Kanalen använder nu standardmallen.
+channel-prompt-preset-changed = Hurra! Kanalen använder nu promptmallen { $name }.
+channel-prompt-preset-not-allowed = Bara serverns administratörer och botens administratörer får byta kanalens promptmall!
+
+## Språk
+
+language-not-allowed = Bara serverns administratörer och botens administratörer får ändra serverns språk!
+language-changed = Hurra! Servern pratar nu svenska.
+language-reset = Hurra! Boten svarar nu på varje användares eget språk.
@@ -0,0 +1,155 @@
+use crate::prelude::*;
+
+use super::{server_sent_events, Backend, FinishReason, GenerationRequest, StreamEvent};
+use futures::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Once, OnceLock},
+};
+
+#[derive(Debug)]
+pub struct KoboldCppBackend {
+    client: reqwest::Client,
+    url: String,
+    context_size: OnceLock<usize>,
+    frequency_penalty_dropped: Once,
+}
+
+#[derive(Debug, Serialize)]
+struct GenerateRequest {
+    prompt: String,
+    max_length: u16,
+    max_context_length: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rep_pen: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampler_seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logit_bias: Option<HashMap<String, f32>>,
+    stop_sequence: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MaxContextLengthResponse {
+    value: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    #[serde(default)]
+    token: String,
+    finish_reason: Option<String>,
+}
+
+impl KoboldCppBackend {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            context_size: OnceLock::new(),
+            frequency_penalty_dropped: Once::new(),
+        }
+    }
+}
+
+impl Backend for KoboldCppBackend {
+    fn stream(&self, request: GenerationRequest) -> BoxStream<'_, Result<StreamEvent>> {
+        async move {
+            if request.sampler.frequency_penalty.is_some() {
+                self.frequency_penalty_dropped.call_once(|| {
+                    tracing::warn!("KoboldCpp has no frequency penalty, leaving it out");
+                });
+            }
+            let template = CONFIG.read().instruct_template();
+            let body = GenerateRequest {
+                prompt: request.text_prompt(&template),
+                max_length: request.max_tokens,
+                max_context_length: self
+                    .context_size
+                    .get()
+                    .copied()
+                    .unwrap_or_else(|| CONFIG.read().context_size()),
+                temperature: request.sampler.temperature,
+                top_p: request.sampler.top_p,
+                top_k: request.sampler.top_k,
+                min_p: request.sampler.min_p,
+                rep_pen: request.sampler.repetition_penalty,
+                presence_penalty: request.sampler.presence_penalty,
+                sampler_seed: request.sampler.seed,
+                logit_bias: request
+                    .sampler
+                    .logit_bias
+                    .clone()
+                    .map(|biases| biases.into_iter().collect()),
+                stop_sequence: request.stop_sequences(Some(&template)),
+            };
+            let response = self
+                .client
+                .post(format!(
+                    "{}/api/extra/generate/stream",
+                    self.url.trim_end_matches('/')
+                ))
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(server_sent_events(response)
+                .and_then(|data| {
+                    futures::future::ready(
+                        serde_json::from_str::<GenerateResponse>(&data).map_err(Into::into),
+                    )
+                })
+                .map_ok(|response| stream::iter(stream_events(response).into_iter().map(Ok)))
+                .try_flatten())
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+
+    fn context_size(&self) -> BoxFuture<'_, Result<Option<usize>>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get(format!(
+                    "{}/api/extra/true_max_context_length",
+                    self.url.trim_end_matches('/')
+                ))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<MaxContextLengthResponse>()
+                .await?;
+            Ok(Some(*self.context_size.get_or_init(|| response.value)))
+        })
+    }
+}
+
+fn stream_events(response: GenerateResponse) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    if !response.token.is_empty() {
+        events.push(StreamEvent::Delta(response.token));
+    }
+    if let Some(finish_reason) = response.finish_reason {
+        events.push(StreamEvent::Finished(match finish_reason.as_str() {
+            "length" => FinishReason::Length,
+            "stop" => FinishReason::Stop,
+            _ => FinishReason::Other,
+        }));
+    }
+    events
+}
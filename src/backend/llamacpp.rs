@@ -0,0 +1,145 @@
+use crate::prelude::*;
+
+use super::{server_sent_events, Backend, FinishReason, GenerationRequest, StreamEvent, Usage};
+use futures::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct LlamaCppBackend {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CompletionRequest {
+    prompt: String,
+    n_predict: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logit_bias: Option<Vec<(String, f32)>>,
+    stop: Vec<String>,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PropsResponse {
+    default_generation_settings: GenerationSettings,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerationSettings {
+    n_ctx: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    stop: bool,
+    #[serde(default)]
+    stopped_limit: bool,
+    tokens_evaluated: Option<usize>,
+    tokens_predicted: Option<usize>,
+}
+
+impl LlamaCppBackend {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+impl Backend for LlamaCppBackend {
+    fn stream(&self, request: GenerationRequest) -> BoxStream<'_, Result<StreamEvent>> {
+        async move {
+            let template = CONFIG.read().instruct_template();
+            let body = CompletionRequest {
+                prompt: request.text_prompt(&template),
+                n_predict: request.max_tokens,
+                temperature: request.sampler.temperature,
+                top_p: request.sampler.top_p,
+                top_k: request.sampler.top_k,
+                min_p: request.sampler.min_p,
+                repeat_penalty: request.sampler.repetition_penalty,
+                frequency_penalty: request.sampler.frequency_penalty,
+                presence_penalty: request.sampler.presence_penalty,
+                seed: request.sampler.seed,
+                logit_bias: request.sampler.logit_bias.clone(),
+                stop: request.stop_sequences(Some(&template)),
+                stream: true,
+            };
+            let response = self
+                .client
+                .post(format!("{}/completion", self.url.trim_end_matches('/')))
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(server_sent_events(response)
+                .and_then(|data| {
+                    futures::future::ready(
+                        serde_json::from_str::<CompletionResponse>(&data).map_err(Into::into),
+                    )
+                })
+                .map_ok(|response| stream::iter(stream_events(response).into_iter().map(Ok)))
+                .try_flatten())
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+
+    fn context_size(&self) -> BoxFuture<'_, Result<Option<usize>>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get(format!("{}/props", self.url.trim_end_matches('/')))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<PropsResponse>()
+                .await?;
+            Ok(Some(response.default_generation_settings.n_ctx))
+        })
+    }
+}
+
+fn stream_events(response: CompletionResponse) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    if !response.content.is_empty() {
+        events.push(StreamEvent::Delta(response.content));
+    }
+    if response.stop {
+        events.push(StreamEvent::Finished(if response.stopped_limit {
+            FinishReason::Length
+        } else {
+            FinishReason::Stop
+        }));
+        events.push(StreamEvent::Usage(Usage {
+            prompt_tokens: response.tokens_evaluated.unwrap_or_default(),
+            completion_tokens: response.tokens_predicted.unwrap_or_default(),
+        }));
+    }
+    events
+}
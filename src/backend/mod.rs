@@ -0,0 +1,208 @@
+pub mod koboldcpp;
+pub mod llamacpp;
+pub mod ollama;
+pub mod openai;
+
+use crate::prelude::*;
+
+use crate::{config::BackendConfig, instruct::InstructTemplate, sampler::SamplerSettings};
+use async_openai::types::Role;
+use futures::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use itertools::Itertools;
+use koboldcpp::KoboldCppBackend;
+use llamacpp::LlamaCppBackend;
+use ollama::OllamaBackend;
+use openai::OpenAiBackend;
+use serde::{Deserialize, Serialize};
+
+pub trait Backend: std::fmt::Debug + Send + Sync {
+    fn stream(&self, request: GenerationRequest) -> BoxStream<'_, Result<StreamEvent>>;
+
+    /// The context window the server was started with, for backends that can
+    /// report it. Others are looked up in `context_sizes` by model name.
+    fn context_size(&self) -> BoxFuture<'_, Result<Option<usize>>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn complete(&self, request: GenerationRequest) -> BoxFuture<'_, Result<Generation>> {
+        Box::pin(async move {
+            let mut generation = Generation::default();
+            let mut stream = self.stream(request);
+            while let Some(event) = stream.next().await {
+                generation.push(event?);
+            }
+            Ok(generation)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenerationRequest {
+    pub messages: Vec<SuperMessage>,
+    pub character_name: String,
+    pub max_tokens: u16,
+    pub sampler: SamplerSettings,
+    pub prefill: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    Delta(String),
+    Finished(FinishReason),
+    Usage(Usage),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinishReason {
+    Stop,
+    Length,
+    Cancelled,
+    Other,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Generation {
+    pub text: String,
+    pub finish_reason: Option<FinishReason>,
+    pub usage: Option<Usage>,
+}
+
+pub fn load() -> Box<dyn Backend> {
+    let config = CONFIG.read();
+    match config.backend() {
+        BackendConfig::OpenAi => Box::new(OpenAiBackend::new(
+            config.openai_url().into(),
+            config.openai_key().into(),
+            config.openai_model().into(),
+        )),
+        BackendConfig::Ollama { url, model } => Box::new(OllamaBackend::new(url, model)),
+        BackendConfig::KoboldCpp { url } => Box::new(KoboldCppBackend::new(url)),
+        BackendConfig::LlamaCpp { url } => Box::new(LlamaCppBackend::new(url)),
+    }
+}
+
+impl Generation {
+    pub fn push(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::Delta(text) => self.text.push_str(&text),
+            StreamEvent::Finished(finish_reason) => self.finish_reason = Some(finish_reason),
+            StreamEvent::Usage(usage) => self.usage = Some(usage),
+        }
+    }
+}
+
+impl GenerationRequest {
+    pub fn new(messages: Vec<SuperMessage>, character_name: impl Into<String>) -> Self {
+        let sampler = CONFIG.read().sampler();
+        Self {
+            messages,
+            character_name: character_name.into(),
+            max_tokens: sampler.max_tokens(),
+            sampler,
+            prefill: None,
+        }
+    }
+
+    #[must_use]
+    pub fn sampler(mut self, sampler: SamplerSettings) -> Self {
+        self.max_tokens = sampler.max_tokens();
+        self.sampler = sampler;
+        self
+    }
+
+    #[must_use]
+    pub const fn max_tokens(mut self, max_tokens: u16) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    #[must_use]
+    pub fn prefill(mut self, text: impl Into<String>) -> Self {
+        self.prefill = Some(text.into());
+        self
+    }
+
+    pub fn chat_messages(&self) -> Vec<SuperMessage> {
+        self.messages
+            .iter()
+            .cloned()
+            .chain(
+                self.prefill.as_ref().map(|text| {
+                    SuperMessage::new_assistant(self.character_name.clone(), text.clone())
+                }),
+            )
+            .collect()
+    }
+
+    pub fn text_prompt(&self, template: &InstructTemplate) -> String {
+        let mut prompt = template.format(&self.messages, &self.character_name);
+        if let Some(prefill) = &self.prefill {
+            if template.include_names {
+                prompt.push(' ');
+            }
+            prompt.push_str(prefill);
+        }
+        prompt
+    }
+
+    pub fn stop_sequences(&self, template: Option<&InstructTemplate>) -> Vec<String> {
+        self.messages
+            .iter()
+            .filter(|message| match message.role {
+                Role::User => true,
+                Role::Assistant => message.author != self.character_name,
+                _ => false,
+            })
+            .map(|message| format!("\n{}:", message.author))
+            .chain(
+                template
+                    .into_iter()
+                    .flat_map(|template| template.stop_sequences.iter().cloned()),
+            )
+            .chain(self.sampler.stop().iter().cloned())
+            .unique()
+            .collect()
+    }
+}
+
+fn lines(response: reqwest::Response) -> BoxStream<'static, Result<String>> {
+    response
+        .bytes_stream()
+        .map_err(crate::error::Error::from)
+        .scan(Vec::new(), |buffer, chunk| {
+            let lines = chunk.map(|bytes| {
+                buffer.extend_from_slice(&bytes);
+                let mut lines = Vec::new();
+                while let Some(index) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line = buffer.drain(..=index).collect_vec();
+                    lines.push(String::from_utf8_lossy(&line).trim().to_string());
+                }
+                lines
+            });
+            futures::future::ready(Some(lines))
+        })
+        .map_ok(|lines| stream::iter(lines.into_iter().map(Ok)))
+        .try_flatten()
+        .try_filter(|line| futures::future::ready(!line.is_empty()))
+        .boxed()
+}
+
+fn server_sent_events(response: reqwest::Response) -> BoxStream<'static, Result<String>> {
+    lines(response)
+        .try_filter_map(|line| {
+            futures::future::ready(Ok(line
+                .strip_prefix("data:")
+                .map(|data| data.trim_start().to_string())))
+        })
+        .boxed()
+}
@@ -0,0 +1,154 @@
+use crate::prelude::*;
+
+use super::{lines, Backend, FinishReason, GenerationRequest, StreamEvent, Usage};
+use async_openai::types::Role;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct OllamaBackend {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    options: Options,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Options {
+    num_predict: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    stop: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<usize>,
+    eval_count: Option<usize>,
+}
+
+impl OllamaBackend {
+    pub fn new(url: String, model: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            model,
+        }
+    }
+}
+
+impl Backend for OllamaBackend {
+    fn stream(&self, request: GenerationRequest) -> BoxStream<'_, Result<StreamEvent>> {
+        async move {
+            let body = ChatRequest {
+                model: self.model.clone(),
+                options: Options {
+                    num_predict: request.max_tokens,
+                    temperature: request.sampler.temperature,
+                    top_p: request.sampler.top_p,
+                    top_k: request.sampler.top_k,
+                    min_p: request.sampler.min_p,
+                    repeat_penalty: request.sampler.repetition_penalty,
+                    frequency_penalty: request.sampler.frequency_penalty,
+                    presence_penalty: request.sampler.presence_penalty,
+                    seed: request.sampler.seed,
+                    stop: request.stop_sequences(None),
+                },
+                messages: request
+                    .chat_messages()
+                    .into_iter()
+                    .map(ChatMessage::from)
+                    .collect(),
+                stream: true,
+            };
+            let response = self
+                .client
+                .post(format!("{}/api/chat", self.url.trim_end_matches('/')))
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(lines(response)
+                .and_then(|line| {
+                    futures::future::ready(
+                        serde_json::from_str::<ChatResponse>(&line).map_err(Into::into),
+                    )
+                })
+                .map_ok(|response| stream::iter(stream_events(response).into_iter().map(Ok)))
+                .try_flatten())
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+}
+
+impl From<SuperMessage> for ChatMessage {
+    fn from(message: SuperMessage) -> Self {
+        let role = match message.role {
+            Role::System => "system",
+            Role::Assistant => "assistant",
+            _ => "user",
+        };
+        Self {
+            role: role.to_string(),
+            content: message.message,
+        }
+    }
+}
+
+fn stream_events(response: ChatResponse) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    if let Some(message) = response.message {
+        if !message.content.is_empty() {
+            events.push(StreamEvent::Delta(message.content));
+        }
+    }
+    if response.done {
+        events.push(StreamEvent::Finished(
+            match response.done_reason.as_deref() {
+                Some("length") => FinishReason::Length,
+                Some("stop") | None => FinishReason::Stop,
+                Some(_) => FinishReason::Other,
+            },
+        ));
+        events.push(StreamEvent::Usage(Usage {
+            prompt_tokens: response.prompt_eval_count.unwrap_or_default(),
+            completion_tokens: response.eval_count.unwrap_or_default(),
+        }));
+    }
+    events
+}
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+use super::{Backend, FinishReason, GenerationRequest, StreamEvent, Usage};
+use async_openai::{
+    config::OpenAIConfig,
+    error::OpenAIError,
+    types::{CreateChatCompletionRequestArgs, CreateChatCompletionStreamResponse},
+    Client,
+};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct OpenAiBackend {
+    client: Client<OpenAIConfig>,
+    model: String,
+}
+
+impl OpenAiBackend {
+    pub fn new(url: String, key: String, model: String) -> Self {
+        let config = OpenAIConfig::default().with_api_key(key).with_api_base(url);
+        Self {
+            client: Client::with_config(config),
+            model,
+        }
+    }
+}
+
+impl Backend for OpenAiBackend {
+    fn stream(&self, request: GenerationRequest) -> BoxStream<'_, Result<StreamEvent>> {
+        async move {
+            let mut args = CreateChatCompletionRequestArgs::default();
+            args.model(&self.model)
+                .max_tokens(request.max_tokens)
+                .messages(
+                    request
+                        .chat_messages()
+                        .into_iter()
+                        .map(Into::into)
+                        .collect::<Vec<_>>(),
+                );
+            let sampler = &request.sampler;
+            if let Some(temperature) = sampler.temperature {
+                args.temperature(temperature);
+            }
+            if let Some(top_p) = sampler.top_p {
+                args.top_p(top_p);
+            }
+            if let Some(frequency_penalty) = sampler.frequency_penalty {
+                args.frequency_penalty(frequency_penalty);
+            }
+            if let Some(presence_penalty) = sampler.presence_penalty {
+                args.presence_penalty(presence_penalty);
+            }
+            if let Some(seed) = sampler.seed {
+                args.seed(seed);
+            }
+            if !sampler.stop().is_empty() {
+                args.stop(sampler.stop().to_vec());
+            }
+            if let Some(biases) = &sampler.logit_bias {
+                args.logit_bias(
+                    biases
+                        .iter()
+                        .map(|(token, bias)| (token.clone(), serde_json::Value::from(*bias)))
+                        .collect::<HashMap<_, _>>(),
+                );
+            }
+            let request = args.build()?;
+            let stream = self.client.chat().create_stream(request).await?;
+            Ok(stream
+                .take_while(|result| {
+                    let ended = matches!(
+                        result,
+                        Err(OpenAIError::StreamError(why)) if why == "Stream ended"
+                    );
+                    futures::future::ready(!ended)
+                })
+                .map_err(crate::error::Error::from)
+                .map_ok(|response| stream::iter(stream_events(response).into_iter().map(Ok)))
+                .try_flatten())
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+}
+
+fn stream_events(response: CreateChatCompletionStreamResponse) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    for choice in response.choices {
+        if let Some(content) = choice.delta.content {
+            events.push(StreamEvent::Delta(content));
+        }
+        if let Some(finish_reason) = choice.finish_reason {
+            events.push(StreamEvent::Finished(match finish_reason {
+                async_openai::types::FinishReason::Stop => FinishReason::Stop,
+                async_openai::types::FinishReason::Length => FinishReason::Length,
+                _ => FinishReason::Other,
+            }));
+        }
+    }
+    if let Some(usage) = response.usage {
+        events.push(StreamEvent::Usage(Usage {
+            prompt_tokens: usage.prompt_tokens as usize,
+            completion_tokens: usage.completion_tokens as usize,
+        }));
+    }
+    events
+}
@@ -0,0 +1,58 @@
+use crate::prelude::*;
+
+use poise::serenity_prelude::MessageId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const PREVIEW_LENGTH: usize = 80;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Branch {
+    pub id: MessageId,
+    pub parent: Option<MessageId>,
+    pub root: MessageId,
+    pub character: String,
+    pub turns: usize,
+    pub preview: String,
+}
+
+impl From<&History> for Branch {
+    fn from(history: &History) -> Self {
+        let reply = history
+            .choices
+            .get(history.current_page)
+            .map(|choice| choice.message.replace('\n', " "))
+            .unwrap_or_default();
+        let preview = if reply.chars().count() > PREVIEW_LENGTH {
+            reply
+                .chars()
+                .take(PREVIEW_LENGTH - 1)
+                .chain(['…'])
+                .collect()
+        } else {
+            reply
+        };
+        Self {
+            id: history.id,
+            parent: history.parent,
+            root: history.root(),
+            character: history.character.name.to_string(),
+            turns: history.turns().len() + 1,
+            preview,
+        }
+    }
+}
+
+/// Returns the tips of every branch in a chat tree, oldest first.
+pub fn leaves(branches: &[Branch]) -> Vec<&Branch> {
+    let parents = branches
+        .iter()
+        .filter_map(|branch| branch.parent)
+        .collect::<HashSet<_>>();
+    let mut leaves = branches
+        .iter()
+        .filter(|branch| !parents.contains(&branch.id))
+        .collect::<Vec<_>>();
+    leaves.sort_by_key(|branch| branch.id);
+    leaves
+}
@@ -0,0 +1,640 @@
+use crate::prelude::*;
+
+use crate::{
+    backend::FinishReason,
+    discord::Data,
+    engine,
+    locale::pick,
+    ownership::Manager,
+    render::render_stream,
+    reply::{ReplyMessage, ReplyView, EMBED_LENGTH},
+    split::{separators, split_reply},
+};
+use poise::serenity_prelude::{
+    ChannelId, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateModal,
+    InputTextStyle, ModalInteractionCollector, ReactionType, UserId,
+};
+use poise::Modal;
+use std::time::Duration;
+
+pub const MODAL_LENGTH: usize = 4000;
+const MODAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+pub trait LocalizedModal: Modal {
+    fn create_localized(
+        defaults: Option<Self>,
+        custom_id: String,
+        locale: &str,
+    ) -> CreateInteractionResponse<'static>;
+}
+
+pub fn modal_input(
+    id: &'static str,
+    label: String,
+    placeholder: String,
+    value: Option<String>,
+    required: bool,
+) -> CreateActionRow<'static> {
+    text_input(
+        InputTextStyle::Short,
+        id,
+        label,
+        placeholder,
+        value,
+        required,
+    )
+}
+
+pub fn modal_paragraph(
+    id: &'static str,
+    label: String,
+    placeholder: String,
+    value: Option<String>,
+    required: bool,
+) -> CreateActionRow<'static> {
+    text_input(
+        InputTextStyle::Paragraph,
+        id,
+        label,
+        placeholder,
+        value,
+        required,
+    )
+}
+
+fn text_input(
+    style: InputTextStyle,
+    id: &'static str,
+    label: String,
+    placeholder: String,
+    value: Option<String>,
+    required: bool,
+) -> CreateActionRow<'static> {
+    let mut input = CreateInputText::new(style, label, id)
+        .placeholder(placeholder)
+        .required(required);
+    if let Some(value) = value {
+        input = input.value(value);
+    }
+    CreateActionRow::InputText(input)
+}
+
+pub async fn execute_localized_modal<M: LocalizedModal>(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    defaults: Option<M>,
+    locale: &str,
+) -> Result<Option<M>> {
+    let custom_id = interaction.id.to_string();
+    interaction
+        .create_response(
+            &ctx.http,
+            M::create_localized(defaults, custom_id.clone(), locale),
+        )
+        .await?;
+    let Some(response) = ModalInteractionCollector::new(ctx.shard.clone())
+        .filter(move |response| response.data.custom_id == custom_id)
+        .timeout(MODAL_TIMEOUT)
+        .await
+    else {
+        return Ok(None);
+    };
+    response
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+    Ok(Some(
+        M::parse(response.data.clone()).map_err(serenity::Error::Other)?,
+    ))
+}
+
+#[derive(Debug, Clone, Modal)]
+#[name = "Redigera meddelandet"]
+pub struct EditMessageModal {
+    #[name = "Innehåll"]
+    #[placeholder = "Meddelandets innehåll…"]
+    pub message: String,
+    #[name = "Fortsättning"]
+    #[placeholder = "Resten av ett långt meddelande…"]
+    pub continuation: Option<String>,
+}
+
+impl LocalizedModal for EditMessageModal {
+    fn create_localized(
+        defaults: Option<Self>,
+        custom_id: String,
+        locale: &str,
+    ) -> CreateInteractionResponse<'static> {
+        let (message, continuation) = defaults
+            .map(|defaults| (Some(defaults.message), defaults.continuation))
+            .unwrap_or_default();
+        let modal =
+            CreateModal::new(custom_id, tr!(locale, "edit-message-modal")).components(vec![
+                modal_input(
+                    "message",
+                    tr!(locale, "edit-message-content"),
+                    tr!(locale, "edit-message-content-placeholder"),
+                    message,
+                    true,
+                ),
+                modal_input(
+                    "continuation",
+                    tr!(locale, "edit-message-continuation"),
+                    tr!(locale, "edit-message-continuation-placeholder"),
+                    continuation,
+                    false,
+                ),
+            ]);
+        CreateInteractionResponse::Modal(modal)
+    }
+}
+
+impl EditMessageModal {
+    /// Splits `text` over the two fields, along with the whitespace between
+    /// them so that [`Self::text`] can join them back the way they were.
+    fn new(text: &str) -> Option<(Self, String)> {
+        let pages = split_reply(text, MODAL_LENGTH);
+        let separator = separators(text, &pages)
+            .pop()
+            .unwrap_or_else(|| "\n\n".into());
+        let mut pages = pages.into_iter();
+        let modal = Self {
+            message: pages.next().unwrap_or_default(),
+            continuation: pages.next(),
+        };
+        pages.next().is_none().then_some((modal, separator))
+    }
+
+    fn text(self, separator: &str) -> String {
+        match self.continuation {
+            Some(continuation) if !continuation.trim().is_empty() => {
+                format!("{}{separator}{continuation}", self.message)
+            }
+            _ => self.message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Prev,
+    Next,
+    Continue,
+    Pin,
+    Edit,
+    Stop,
+}
+
+impl Button {
+    const ALL: [Self; 6] = [
+        Self::Prev,
+        Self::Next,
+        Self::Continue,
+        Self::Pin,
+        Self::Edit,
+        Self::Stop,
+    ];
+    const CHAT: [Self; 5] = [
+        Self::Prev,
+        Self::Next,
+        Self::Continue,
+        Self::Pin,
+        Self::Edit,
+    ];
+
+    const fn suffix(self) -> &'static str {
+        match self {
+            Self::Prev => "prev",
+            Self::Next => "next",
+            Self::Continue => "continue",
+            Self::Pin => "pin",
+            Self::Edit => "edit",
+            Self::Stop => "stop",
+        }
+    }
+
+    const fn emoji(self) -> &'static str {
+        match self {
+            Self::Prev => "◀",
+            Self::Next => "▶",
+            Self::Continue => "⏩",
+            Self::Pin => "📌",
+            Self::Edit => "✏️",
+            Self::Stop => "⏹",
+        }
+    }
+
+    pub fn parse(custom_id: &str) -> Option<(&str, Self)> {
+        Self::ALL.into_iter().find_map(|button| {
+            let prefix = custom_id.strip_suffix(button.suffix())?;
+            prefix.parse::<u64>().ok().map(|_| (prefix, button))
+        })
+    }
+
+    fn create(self, prefix: &str, disabled: bool) -> CreateButton<'static> {
+        CreateButton::new(format!("{prefix}{}", self.suffix()))
+            .emoji(ReactionType::try_from(self.emoji().to_string()).expect("valid emoji"))
+            .disabled(disabled)
+    }
+}
+
+pub fn create_buttons(
+    prefix: impl ToString,
+) -> (Vec<CreateActionRow<'static>>, Vec<CreateActionRow<'static>>) {
+    let prefix = prefix.to_string();
+    let buttons = |disabled| {
+        vec![CreateActionRow::Buttons(
+            Button::CHAT
+                .into_iter()
+                .map(|button| button.create(&prefix, disabled))
+                .collect(),
+        )]
+    };
+    (buttons(false), buttons(true))
+}
+
+pub fn create_streaming_buttons(prefix: impl ToString) -> Vec<CreateActionRow<'static>> {
+    let prefix = prefix.to_string();
+    vec![
+        CreateActionRow::Buttons(
+            Button::CHAT
+                .into_iter()
+                .map(|button| button.create(&prefix, true))
+                .collect(),
+        ),
+        CreateActionRow::Buttons(vec![Button::Stop.create(&prefix, false)]),
+    ]
+}
+
+/// The footer under a reply: its page or label, how long it took, how long it
+/// is and how much of the context it fills, and why it stopped.
+pub fn reply_footer(
+    locale: &str,
+    data: &Data,
+    history: &History,
+    label: &str,
+    text: &str,
+    seconds: f64,
+    finish_reason: Option<FinishReason>,
+) -> String {
+    let finish = match finish_reason {
+        Some(FinishReason::Stop) => "stop",
+        Some(FinishReason::Length) => "length",
+        Some(FinishReason::Cancelled) => "cancelled",
+        Some(FinishReason::Other) | None => "other",
+    };
+    tr!(
+        locale,
+        "reply-footer",
+        label = label,
+        seconds = seconds,
+        characters = text.chars().count(),
+        tokens = history.prompt_tokens + data.tokenizer.count(text),
+        context = data.context_size,
+        finish = finish,
+    )
+}
+
+pub fn page_footer(locale: &str, data: &Data, history: &History) -> String {
+    let page = history.current_page;
+    reply_footer(
+        locale,
+        data,
+        history,
+        &format!("{}/{}", page + 1, history.choices.len()),
+        &history.choices[page].message,
+        history.seconds_taken[page],
+        history.finish_reason(page),
+    )
+}
+
+pub async fn handle_button(
+    ctx: FrameworkContext<'_>,
+    interaction: &ComponentInteraction,
+) -> Result<()> {
+    let Some((prefix, button)) = Button::parse(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+    let data = ctx.user_data();
+    let http = &ctx.serenity_context.http;
+    let locale = pick(
+        &data,
+        interaction.guild_id,
+        Some(interaction.locale.as_str()),
+    );
+    if button == Button::Stop {
+        let requester = engine::generation_requester(&data, interaction.message.id);
+        let manager = Manager::from_interaction(interaction);
+        if requester.is_some_and(|requester| requester != interaction.user.id)
+            && !manager.guild_admin
+            && !manager.bot_admin
+        {
+            let response = CreateInteractionResponseMessage::new()
+                .content(tr!(locale, "stop-not-allowed"))
+                .ephemeral(true);
+            interaction
+                .create_response(http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+        engine::stop_generation(&data, interaction.message.id);
+        interaction
+            .create_response(http, CreateInteractionResponse::Acknowledge)
+            .await?;
+        return Ok(());
+    }
+    let Some(_busy) = engine::claim_message(&data, interaction.message.id) else {
+        let response = CreateInteractionResponseMessage::new()
+            .content(tr!(locale, "message-busy"))
+            .ephemeral(true);
+        interaction
+            .create_response(http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+    let Some(mut history) = data.history(&interaction.message)? else {
+        return Ok(());
+    };
+    if history.choices.is_empty() {
+        return Ok(());
+    }
+    history.current_page = history.current_page.min(history.choices.len() - 1);
+    let mut message =
+        ReplyMessage::from_message(http, &data, (*interaction.message).clone()).await?;
+    let (enabled_buttons, disabled_buttons) = create_buttons(prefix);
+
+    match button {
+        Button::Pin => {
+            let pages = split_reply(&history.choices[history.current_page].message, EMBED_LENGTH);
+            let mut pinned_message = None;
+            for (index, page) in pages.into_iter().enumerate() {
+                let mut embed = CreateEmbed::new().description(page);
+                if index == 0 {
+                    embed = embed
+                        .title(history.character.to_string())
+                        .thumbnail(history.character.avatar.to_string());
+                }
+                let sent = interaction
+                    .channel_id
+                    .send_message(
+                        http,
+                        CreateMessage::new()
+                            .embed(embed)
+                            .reference_message(&*interaction.message),
+                    )
+                    .await?;
+                pinned_message.get_or_insert(sent);
+            }
+            if let Some(pinned_message) = pinned_message {
+                pinned_message.pin(http, None).await?;
+            }
+            interaction
+                .create_response(http, CreateInteractionResponse::Acknowledge)
+                .await?;
+        }
+        Button::Edit => {
+            let page = history.current_page;
+            let Some((defaults, separator)) = EditMessageModal::new(&history.choices[page].message)
+            else {
+                let response = CreateInteractionResponseMessage::new()
+                    .content(tr!(locale, "message-too-long-to-edit"))
+                    .ephemeral(true);
+                interaction
+                    .create_response(http, CreateInteractionResponse::Message(response))
+                    .await?;
+                return Ok(());
+            };
+            let user_name = substitute_name(interaction.user.name.clone());
+            let view = ReplyView::new(
+                history.choices[page].message.to_string(),
+                page_footer(locale, &data, &history),
+            );
+            message
+                .edit(
+                    http,
+                    &data,
+                    &history.character,
+                    view.clone()
+                        .note(
+                            tr!(locale, "message-being-edited"),
+                            tr!(locale, "message-being-edited-by", user = user_name),
+                        )
+                        .components(disabled_buttons),
+                )
+                .await?;
+            let modal = execute_localized_modal::<EditMessageModal>(
+                ctx.serenity_context,
+                interaction,
+                Some(defaults),
+                locale,
+            )
+            .await?;
+            let Some(modal) = modal else {
+                message
+                    .edit(
+                        http,
+                        &data,
+                        &history.character,
+                        view.components(enabled_buttons),
+                    )
+                    .await?;
+                return Ok(());
+            };
+            let text = modal.text(&separator);
+            engine::edit_swipe(&data, &mut history, page, &text)?;
+            let footer = tr!(
+                locale,
+                "edited-footer",
+                footer = page_footer(locale, &data, &history)
+            );
+            message
+                .edit(
+                    http,
+                    &data,
+                    &history.character,
+                    ReplyView::new(text, footer).components(enabled_buttons),
+                )
+                .await?;
+        }
+        Button::Continue => {
+            interaction.defer(http).await?;
+            let channel_id = interaction.channel_id;
+            continue_reply(
+                http,
+                &data,
+                &mut history,
+                &mut message,
+                channel_id,
+                prefix,
+                locale,
+                interaction.user.id,
+            )
+            .await?;
+        }
+        Button::Stop => {}
+        Button::Prev => {
+            interaction.defer(http).await?;
+            let page = history
+                .current_page
+                .checked_sub(1)
+                .unwrap_or(history.choices.len() - 1);
+            engine::select_swipe(&data, &mut history, page)?;
+            show_page(http, &data, &history, &mut message, locale, enabled_buttons).await?;
+        }
+        Button::Next => {
+            interaction.defer(http).await?;
+            let page = history.current_page + 1;
+            if page >= history.choices.len() {
+                let channel_id = interaction.channel_id;
+                regenerate(
+                    http,
+                    &data,
+                    &mut history,
+                    &mut message,
+                    channel_id,
+                    prefix,
+                    locale,
+                    interaction.user.id,
+                )
+                .await?;
+            } else {
+                engine::select_swipe(&data, &mut history, page)?;
+                show_page(http, &data, &history, &mut message, locale, enabled_buttons).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn show_page(
+    http: &serenity::Http,
+    data: &Data,
+    history: &History,
+    message: &mut ReplyMessage,
+    locale: &str,
+    buttons: Vec<CreateActionRow<'static>>,
+) -> Result<()> {
+    let description = history.choices[history.current_page].message.to_string();
+    let footer = page_footer(locale, data, history);
+    message
+        .edit(
+            http,
+            data,
+            &history.character,
+            ReplyView::new(description, footer).components(buttons),
+        )
+        .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn continue_reply(
+    http: &serenity::Http,
+    data: &Data,
+    history: &mut History,
+    message: &mut ReplyMessage,
+    channel_id: ChannelId,
+    prefix: &str,
+    locale: &str,
+    requester: UserId,
+) -> Result<()> {
+    let page = history.current_page;
+    let label = format!("{}/{}", page + 1, history.choices.len());
+    let previous = history.choices[page].message.clone();
+    let stream = engine::stream_continuation(data, history, channel_id)?;
+    show_streaming(http, data, history, message, &previous, &label, prefix).await?;
+    let (enabled_buttons, _) = create_buttons(prefix);
+    let rendered = render_stream(
+        http,
+        data,
+        history,
+        message,
+        stream,
+        &previous,
+        &label,
+        enabled_buttons.clone(),
+        locale,
+        requester,
+    )
+    .await;
+    match rendered {
+        Ok(rendered) => engine::record_continuation(
+            data,
+            history,
+            &rendered.text,
+            rendered.seconds_taken,
+            rendered.finish_reason,
+        ),
+        Err(err) => {
+            show_page(http, data, history, message, locale, enabled_buttons).await?;
+            Err(err)
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn regenerate(
+    http: &serenity::Http,
+    data: &Data,
+    history: &mut History,
+    message: &mut ReplyMessage,
+    channel_id: ChannelId,
+    prefix: &str,
+    locale: &str,
+    requester: UserId,
+) -> Result<()> {
+    let label = format!(
+        "{}/{}",
+        history.choices.len() + 1,
+        history.choices.len() + 1
+    );
+    let stream = engine::stream_reply(data, history, channel_id).await?;
+    show_streaming(http, data, history, message, "", &label, prefix).await?;
+    let (enabled_buttons, _) = create_buttons(prefix);
+    let rendered = render_stream(
+        http,
+        data,
+        history,
+        message,
+        stream,
+        "",
+        &label,
+        enabled_buttons.clone(),
+        locale,
+        requester,
+    )
+    .await;
+    match rendered {
+        Ok(rendered) => engine::record_swipe(
+            data,
+            history,
+            rendered.text,
+            rendered.seconds_taken,
+            rendered.finish_reason,
+        ),
+        Err(err) => {
+            let text = tr!(locale, "generation-failed", error = err.to_string());
+            engine::record_swipe(data, history, text, 0.0, None)?;
+            show_page(http, data, history, message, locale, enabled_buttons).await
+        }
+    }
+}
+
+async fn show_streaming(
+    http: &serenity::Http,
+    data: &Data,
+    history: &History,
+    message: &mut ReplyMessage,
+    previous: &str,
+    label: &str,
+    prefix: &str,
+) -> Result<()> {
+    message
+        .edit(
+            http,
+            data,
+            &history.character,
+            ReplyView::new(format!("{previous}…"), label)
+                .components(create_streaming_buttons(prefix)),
+        )
+        .await
+}
@@ -0,0 +1,82 @@
+use crate::prelude::*;
+
+use dashmap::DashMap;
+use serenity::MessageId;
+use std::time::{Duration, Instant};
+
+const MAX_INTERVAL: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone)]
+pub struct Cadence {
+    base: Duration,
+    interval: Duration,
+    last_flush: Instant,
+}
+
+impl Cadence {
+    pub const fn new(base: Duration, now: Instant) -> Self {
+        Self {
+            base,
+            interval: base,
+            last_flush: now,
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Duration::from_millis(CONFIG.read().stream_interval_ms()),
+            Instant::now(),
+        )
+    }
+
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        now.duration_since(self.last_flush) >= self.interval
+    }
+
+    pub fn flushed(&mut self, now: Instant) {
+        self.set_interval(self.interval * 3 / 4, now);
+    }
+
+    // Discord only reports a reset once the bucket is empty, so edits were
+    // coming faster than it allows; wait the whole reset between them for now.
+    pub fn rate_limited(&mut self, reset_after: Duration, now: Instant) {
+        self.set_interval(reset_after.max(self.interval), now);
+    }
+
+    fn set_interval(&mut self, interval: Duration, now: Instant) {
+        self.interval = interval.clamp(self.base, MAX_INTERVAL.max(self.base));
+        self.last_flush = now;
+    }
+}
+
+/// Bucket resets reported by serenity's ratelimit callback, by the message
+/// whose edit ran into them, along with when the bucket resets. Resets that
+/// have passed are dropped, as nothing will ask for them any more.
+#[derive(Debug, Default)]
+pub struct RateLimits(DashMap<MessageId, (Duration, Instant)>);
+
+impl RateLimits {
+    pub fn record(&self, path: &str, reset_after: Duration, now: Instant) {
+        self.0.retain(|_, (_, reset_at)| *reset_at > now);
+        if let Some(message_id) = edited_message(path) {
+            self.0.insert(message_id, (reset_after, now + reset_after));
+        }
+    }
+
+    pub fn take(&self, message_id: MessageId, now: Instant) -> Option<Duration> {
+        self.0
+            .remove(&message_id)
+            .filter(|(_, (_, reset_at))| *reset_at > now)
+            .map(|(_, (reset_after, _))| reset_after)
+    }
+}
+
+fn edited_message(path: &str) -> Option<MessageId> {
+    let (_, rest) = path.split_once("/messages/")?;
+    let id = rest.split(['/', '?']).next()?.parse::<u64>().ok()?;
+    (id != 0).then(|| MessageId::new(id))
+}
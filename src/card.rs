@@ -0,0 +1,350 @@
+use crate::prelude::*;
+
+use crate::character::ExampleBlock;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::read::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::Read;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const MAX_TEXT_LENGTH: u64 = 16 * 1024 * 1024;
+
+pub const BLANK_PNG: &[u8] = b"\x89\x50\x4e\x47\x0d\x0a\x1a\x0a\x00\x00\x00\x0d\x49\x48\x44\x52\x00\x00\x00\x01\x00\x00\x00\x01\x08\x06\x00\x00\x00\x1f\x15\xc4\x89\x00\x00\x00\x0b\x49\x44\x41\x54\x78\x9c\x63\x60\x00\x02\x00\x00\x05\x00\x01\x7a\x5e\xab\x3f\x00\x00\x00\x00\x49\x45\x4e\x44\xae\x42\x60\x82";
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CardData {
+    pub name: String,
+    pub description: String,
+    pub personality: String,
+    pub scenario: String,
+    pub first_mes: String,
+    pub mes_example: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    pub creator_notes: String,
+    pub system_prompt: String,
+    pub post_history_instructions: String,
+    pub alternate_greetings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character_book: Option<Value>,
+    pub tags: Vec<String>,
+    pub creator: String,
+    pub character_version: String,
+    pub extensions: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CardV2 {
+    pub spec: String,
+    pub spec_version: String,
+    pub data: CardData,
+}
+
+#[derive(Debug)]
+pub struct ImportedCharacter {
+    pub character: Character,
+    pub unmapped_fields: Vec<&'static str>,
+}
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(PNG_SIGNATURE)
+}
+
+impl CardData {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if is_png(bytes) {
+            Self::from_png(bytes)
+        } else {
+            Self::from_json(bytes)
+        }
+    }
+
+    pub fn from_png(bytes: &[u8]) -> Result<Self> {
+        let text_chunks = png_text_chunks(bytes)?;
+        let encoded = text_chunks
+            .iter()
+            .find(|(keyword, _)| keyword == "ccv3")
+            .or_else(|| text_chunks.iter().find(|(keyword, _)| keyword == "chara"))
+            .map(|(_, text)| text)
+            .ok_or(crate::error::Error::MissingCard)?;
+        Self::from_json(&STANDARD.decode(encoded.trim())?)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let mut card = serde_json::from_slice::<Value>(bytes)?;
+        let is_wrapped = card
+            .get("spec")
+            .and_then(Value::as_str)
+            .is_some_and(|spec| spec.starts_with("chara_card_v"));
+        if is_wrapped {
+            if let Some(data) = card.get_mut("data") {
+                card = data.take();
+            }
+        }
+        Ok(serde_json::from_value(card)?)
+    }
+
+    pub fn into_character(self, avatar: Option<String>, locale: &str) -> ImportedCharacter {
+        let unmapped_fields = self.unmapped_fields();
+        let description = [
+            self.description,
+            labelled(&tr!(locale, "card-personality"), self.personality),
+            labelled(&tr!(locale, "card-scenario"), self.scenario),
+        ]
+        .into_iter()
+        .filter(|section| !section.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+        let greeting = Some(self.first_mes).filter(|greeting| !greeting.trim().is_empty());
+        let description = Some(description).filter(|description| !description.is_empty());
+        let avatar = avatar.or_else(|| {
+            self.avatar
+                .filter(|avatar| avatar.starts_with("http://") || avatar.starts_with("https://"))
+        });
+        let mut character = Character::new(self.name.clone(), greeting, description, None, avatar);
+        character.set_examples(parse_example_blocks(&self.mes_example));
+        ImportedCharacter {
+            character,
+            unmapped_fields,
+        }
+    }
+
+    pub fn to_v2_json(&self) -> Result<Vec<u8>> {
+        let card = CardV2 {
+            spec: "chara_card_v2".into(),
+            spec_version: "2.0".into(),
+            data: self.clone(),
+        };
+        Ok(serde_json::to_vec_pretty(&card)?)
+    }
+
+    pub fn to_png(&self, base_png: &[u8]) -> Result<Vec<u8>> {
+        embed_in_png(base_png, &self.to_v2_json()?)
+    }
+
+    fn unmapped_fields(&self) -> Vec<&'static str> {
+        [
+            ("creator_notes", self.creator_notes.trim().is_empty()),
+            ("system_prompt", self.system_prompt.trim().is_empty()),
+            (
+                "post_history_instructions",
+                self.post_history_instructions.trim().is_empty(),
+            ),
+            ("alternate_greetings", self.alternate_greetings.is_empty()),
+            ("character_book", self.character_book.is_none()),
+            ("tags", self.tags.is_empty()),
+            ("creator", self.creator.trim().is_empty()),
+            (
+                "character_version",
+                self.character_version.trim().is_empty(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, is_empty)| !is_empty)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+fn labelled(label: &str, section: String) -> String {
+    if section.trim().is_empty() {
+        section
+    } else {
+        format!("{label}: {section}")
+    }
+}
+
+impl From<Character> for CardData {
+    fn from(character: Character) -> Self {
+        let name = character.name.to_string();
+        Self {
+            mes_example: format_example_blocks(character.examples()),
+            name,
+            description: character.description.message,
+            first_mes: character.greeting.message,
+            avatar: Some(character.avatar.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn format_example_blocks(blocks: &[ExampleBlock]) -> String {
+    blocks
+        .iter()
+        .map(|block| {
+            let lines = block
+                .turns
+                .iter()
+                .flat_map(|turn| {
+                    let user = turn.user.iter().map(|text| format!("{{{{user}}}}: {text}"));
+                    let character = turn
+                        .character
+                        .iter()
+                        .map(|text| format!("{{{{char}}}}: {text}"));
+                    user.chain(character)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("<START>\n{lines}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn parse_example_blocks(input: &str) -> Vec<ExampleBlock> {
+    let mut blocks = Vec::new();
+    for chunk in input.split("<START>") {
+        let mut messages: Vec<(bool, String)> = Vec::new();
+        for line in chunk.lines() {
+            if let Some(message) = line.trim_start().strip_prefix("{{user}}:") {
+                messages.push((true, message.trim().to_string()));
+            } else if let Some(message) = line.trim_start().strip_prefix("{{char}}:") {
+                messages.push((false, message.trim().to_string()));
+            } else if let Some((_, last)) = messages.last_mut() {
+                last.push('\n');
+                last.push_str(line);
+            }
+        }
+        let mut block = ExampleBlock::default();
+        for (from_user, mut message) in messages {
+            message.truncate(message.trim_end().len());
+            if from_user {
+                block.push_user(message);
+            } else {
+                block.push_character(message);
+            }
+        }
+        if !block.turns.is_empty() {
+            blocks.push(block);
+        }
+    }
+    blocks
+}
+
+struct PngChunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+}
+
+impl PngChunk<'_> {
+    /// The keyword and text of a `tEXt`, `zTXt` or `iTXt` chunk.
+    fn text(&self) -> Option<(String, String)> {
+        let (keyword, rest) = split_at_nul(self.data)?;
+        let keyword = String::from_utf8_lossy(keyword).into_owned();
+        let text = match self.kind {
+            b"tEXt" => rest.to_vec(),
+            b"zTXt" => {
+                let (&method, compressed) = rest.split_first()?;
+                (method == 0).then(|| inflate(compressed))??
+            }
+            b"iTXt" => {
+                let [compressed, method, rest @ ..] = rest else {
+                    return None;
+                };
+                let (_language, rest) = split_at_nul(rest)?;
+                let (_translated_keyword, text) = split_at_nul(rest)?;
+                match (compressed, method) {
+                    (0, _) => text.to_vec(),
+                    (1, 0) => inflate(text)?,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some((keyword, String::from_utf8_lossy(&text).into_owned()))
+    }
+
+    fn write_to(&self, output: &mut Vec<u8>) -> Result<()> {
+        let length =
+            u32::try_from(self.data.len()).map_err(|_| crate::error::Error::CardTooLarge)?;
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(self.kind);
+        hasher.update(self.data);
+        output.extend_from_slice(&length.to_be_bytes());
+        output.extend_from_slice(self.kind);
+        output.extend_from_slice(self.data);
+        output.extend_from_slice(&hasher.finalize().to_be_bytes());
+        Ok(())
+    }
+}
+
+fn split_at_nul(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let separator = bytes.iter().position(|&byte| byte == 0)?;
+    Some((&bytes[..separator], &bytes[separator + 1..]))
+}
+
+/// Decompresses zlib data, giving up on anything that inflates past
+/// [`MAX_TEXT_LENGTH`].
+fn inflate(compressed: &[u8]) -> Option<Vec<u8>> {
+    let mut text = Vec::new();
+    ZlibDecoder::new(compressed)
+        .take(MAX_TEXT_LENGTH + 1)
+        .read_to_end(&mut text)
+        .ok()?;
+    (text.len() as u64 <= MAX_TEXT_LENGTH).then_some(text)
+}
+
+fn png_chunks(bytes: &[u8]) -> Result<Vec<PngChunk<'_>>> {
+    let mut rest = bytes
+        .strip_prefix(PNG_SIGNATURE)
+        .ok_or(crate::error::Error::MissingCard)?;
+    let mut chunks = Vec::new();
+    while rest.len() >= 12 {
+        let (length, after_length) = rest.split_at(4);
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let (kind, after_kind) = after_length.split_at(4);
+        if after_kind.len() < length + 4 {
+            break;
+        }
+        let (data, after_data) = after_kind.split_at(length);
+        chunks.push(PngChunk { kind, data });
+        if kind == b"IEND" {
+            break;
+        }
+        rest = &after_data[4..];
+    }
+    Ok(chunks)
+}
+
+fn png_text_chunks(bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    Ok(png_chunks(bytes)?
+        .iter()
+        .filter_map(PngChunk::text)
+        .collect())
+}
+
+pub fn embed_in_png(png: &[u8], json: &[u8]) -> Result<Vec<u8>> {
+    let encoded = STANDARD.encode(json);
+    let text = [b"chara\0".as_slice(), encoded.as_bytes()].concat();
+    let card_chunk = PngChunk {
+        kind: b"tEXt",
+        data: &text,
+    };
+    let mut output = PNG_SIGNATURE.to_vec();
+    let mut ended = false;
+    for chunk in png_chunks(png)? {
+        let is_card = chunk
+            .text()
+            .is_some_and(|(keyword, _)| keyword == "chara" || keyword == "ccv3");
+        if is_card {
+            continue;
+        }
+        if chunk.kind == b"IEND" {
+            card_chunk.write_to(&mut output)?;
+            ended = true;
+        }
+        chunk.write_to(&mut output)?;
+    }
+    // A truncated PNG still gets its card, followed by the end it was missing.
+    if !ended {
+        card_chunk.write_to(&mut output)?;
+        PngChunk {
+            kind: b"IEND",
+            data: &[],
+        }
+        .write_to(&mut output)?;
+    }
+    Ok(output)
+}
@@ -0,0 +1,49 @@
+use crate::{buttons::continue_reply, engine, prelude::*, reply::ReplyMessage};
+use poise::serenity_prelude::GetMessages;
+
+#[poise::command(slash_command, prefix_command)]
+pub async fn fortsätt(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let messages = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().limit(100))
+        .await?;
+    let Some(latest) = data.latest_history(&messages)? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let Some(message) = messages.iter().find(|message| message.id == latest.id) else {
+        ctx.say(tr!(locale, "message-not-found")).await?;
+        return Ok(());
+    };
+    let Some(_busy) = engine::claim_message(&data, message.id) else {
+        ctx.say(tr!(locale, "message-busy")).await?;
+        return Ok(());
+    };
+    let Some(mut history) = data.history(message)? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    if history.choices.get(history.current_page).is_none() {
+        ctx.say(tr!(locale, "no-reply-to-continue")).await?;
+        return Ok(());
+    }
+
+    let mut message = ReplyMessage::from_message(ctx.http(), &data, message.clone()).await?;
+    let prefix = history.id.to_string();
+    continue_reply(
+        ctx.http(),
+        &data,
+        &mut history,
+        &mut message,
+        ctx.channel_id(),
+        &prefix,
+        locale,
+        ctx.author().id,
+    )
+    .await?;
+    ctx.say(tr!(locale, "reply-continued")).await?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::{
+    branch::leaves,
+    buttons::{create_buttons, page_footer},
+    engine,
+    prelude::*,
+    reply::{ReplyMessage, ReplyView},
+};
+use itertools::Itertools;
+use poise::serenity_prelude::GetMessages;
+
+const LISTED_BRANCHES: usize = 10;
+
+#[poise::command(slash_command, prefix_command)]
+pub async fn grenar(
+    ctx: Context<'_>,
+    #[description = "Grenen att fortsätta från"]
+    #[min = 1]
+    gren: Option<usize>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let messages = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().limit(100))
+        .await?;
+    let Some(current) = data.latest_history(&messages)? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let mut branches = data.branches(current.root())?;
+    if branches.iter().all(|branch| branch.id != current.id) {
+        branches.push((&current).into());
+    }
+    let leaves = leaves(&branches);
+
+    let Some(gren) = gren else {
+        let list = leaves
+            .iter()
+            .enumerate()
+            .skip(leaves.len().saturating_sub(LISTED_BRANCHES))
+            .map(|(index, branch)| {
+                tr!(
+                    locale,
+                    "branch-line",
+                    number = index + 1,
+                    character = branch.character.clone(),
+                    turns = branch.turns,
+                    preview = branch.preview.clone(),
+                    link = branch.id.link(ctx.channel_id(), ctx.guild_id()),
+                    latest = (branch.id == current.id).to_string(),
+                )
+            })
+            .join("\n");
+        ctx.say(tr!(
+            locale,
+            "branch-list",
+            count = leaves.len(),
+            list = list
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let Some(branch) = gren.checked_sub(1).and_then(|index| leaves.get(index)) else {
+        ctx.say(tr!(locale, "branch-not-found")).await?;
+        return Ok(());
+    };
+    let Some(mut history) = data.storage.history(branch.id)? else {
+        ctx.say(tr!(locale, "branch-not-found")).await?;
+        return Ok(());
+    };
+    let Some(choice) = history.choices.get(history.current_page) else {
+        ctx.say(tr!(locale, "branch-has-no-reply")).await?;
+        return Ok(());
+    };
+    let Ok(reference) = ctx.channel_id().message(ctx.http(), branch.id).await else {
+        ctx.say(tr!(locale, "branch-message-not-found")).await?;
+        return Ok(());
+    };
+
+    let (components, _) = create_buttons(ctx.id());
+    let view = ReplyView::new(choice.message.clone(), page_footer(locale, &data, &history))
+        .components(components);
+    let message =
+        ReplyMessage::send(ctx.http(), &data, &reference, &history.character, view).await?;
+    engine::resume_branch(&data, &mut history, message.id())?;
+    ctx.say(tr!(locale, "branch-resumed", branch = gren))
+        .await?;
+    Ok(())
+}
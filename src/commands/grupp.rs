@@ -0,0 +1,229 @@
+use crate::{
+    group::{Group, SpeakerStrategy},
+    prelude::*,
+};
+use poise::{
+    serenity_prelude::{CreateEmbed, CreateEmbedFooter, GetMessages},
+    CreateReply,
+};
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands("starta", "lägg_till", "ta_bort", "tysta", "ordning")
+)]
+#[allow(clippy::unused_async)]
+pub async fn grupp(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+async fn starta(
+    ctx: Context<'_>,
+    #[description = "Gubben som hälsar först"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe: String,
+    #[description = "Ytterligare gubbe"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe2: String,
+    #[description = "Ytterligare gubbe"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe3: Option<String>,
+    #[description = "Ytterligare gubbe"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe4: Option<String>,
+    #[description = "Hur nästa talare väljs"] ordning: Option<SpeakerStrategy>,
+) -> Result<()> {
+    let locale = locale(ctx);
+    let mut characters = Vec::<Character>::new();
+    for name in [Some(gubbe), Some(gubbe2), gubbe3, gubbe4]
+        .into_iter()
+        .flatten()
+    {
+        let Some(character) = find_character(ctx, &name)? else {
+            ctx.say(tr!(locale, "named-character-not-found", name = name))
+                .await?;
+            return Ok(());
+        };
+        if characters
+            .iter()
+            .all(|member| member.name.to_string() != character.name.to_string())
+        {
+            characters.push(character);
+        }
+    }
+    if characters.len() < 2 {
+        ctx.say(tr!(locale, "group-needs-two")).await?;
+        return Ok(());
+    }
+
+    let group = Group::new(characters, ordning.unwrap_or_default());
+    let Some(first) = group.members.first().map(|member| member.character.clone()) else {
+        return Ok(());
+    };
+    let embed = CreateEmbed::new()
+        .title(first.to_string())
+        .description(first.greeting.to_string())
+        .thumbnail(first.avatar.to_string())
+        .footer(CreateEmbedFooter::new(tr!(
+            locale,
+            "group-footer",
+            names = group.names()
+        )));
+    let message = ctx.send(CreateReply::default().embed(embed)).await?;
+    if let Some(history) = group.into_history(message.message().await?.id) {
+        ctx.data().insert_history(history)?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lägg_till(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    gubbe: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let Some(mut history) = latest_history(ctx).await? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let Some(character) = find_character(ctx, &gubbe)? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let character_name = character.to_string();
+    if !history.add_group_member(character) {
+        ctx.say(tr!(
+            locale,
+            "group-already-member",
+            character = character_name
+        ))
+        .await?;
+        return Ok(());
+    }
+    ctx.data().insert_history(history)?;
+    ctx.say(tr!(locale, "group-joined", character = character_name))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn ta_bort(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    gubbe: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let Some(mut history) = latest_history(ctx).await? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let Some(character_name) = most_similar_name_to(&gubbe, ctx)? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let Some(character) = history.remove_group_member(&character_name) else {
+        ctx.say(tr!(
+            locale,
+            "group-cannot-remove",
+            character = character_name
+        ))
+        .await?;
+        return Ok(());
+    };
+    ctx.data().insert_history(history)?;
+    ctx.say(tr!(locale, "group-left", character = character.to_string()))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn tysta(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    gubbe: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let Some(mut history) = latest_history(ctx).await? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let Some(character_name) = most_similar_name_to(&gubbe, ctx)? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let Some(muted) = history.toggle_group_member(&character_name) else {
+        ctx.say(tr!(locale, "group-not-member", character = character_name))
+            .await?;
+        return Ok(());
+    };
+    ctx.data().insert_history(history)?;
+    if muted {
+        ctx.say(tr!(locale, "group-muted", character = character_name))
+            .await?;
+    } else {
+        ctx.say(tr!(locale, "group-unmuted", character = character_name))
+            .await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn ordning(
+    ctx: Context<'_>,
+    #[description = "Hur nästa talare väljs"] ordning: SpeakerStrategy,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let Some(mut history) = latest_history(ctx).await? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+    let Some(group) = history.group.as_mut() else {
+        ctx.say(tr!(locale, "not-a-group-chat")).await?;
+        return Ok(());
+    };
+    group.strategy = ordning;
+    ctx.data().insert_history(history)?;
+    ctx.say(tr!(
+        locale,
+        "speaker-strategy-changed",
+        strategy = strategy_name(locale, ordning)
+    ))
+    .await?;
+    Ok(())
+}
+
+fn strategy_name(locale: &str, strategy: SpeakerStrategy) -> String {
+    match strategy {
+        SpeakerStrategy::RoundRobin => tr!(locale, "speaker-strategy-round-robin"),
+        SpeakerStrategy::Mention => tr!(locale, "speaker-strategy-mention"),
+        SpeakerStrategy::Natural => tr!(locale, "speaker-strategy-natural"),
+    }
+}
+
+fn find_character(ctx: Context<'_>, name: &str) -> Result<Option<Character>> {
+    let Some(most_similar_name) = most_similar_name_to(name, ctx)? else {
+        return Ok(None);
+    };
+    ctx.data().character(&most_similar_name, ctx.guild_id())
+}
+
+async fn latest_history(ctx: Context<'_>) -> Result<Option<History>> {
+    let messages = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().limit(100))
+        .await?;
+    ctx.data().latest_history(&messages)
+}
@@ -11,11 +11,11 @@ use poise::{
 
 #[poise::command(slash_command, prefix_command)]
 pub async fn gubbar(ctx: Context<'_>) -> Result<()> {
-    let characters = ctx.data().characters();
+    let locale = locale(ctx);
+    let characters = ctx.data().characters(ctx.guild_id())?;
 
     if characters.is_empty() {
-        ctx.say("Du har inga gubbar, verkar det som! Eller så hittades inga :(")
-            .await?;
+        ctx.say(tr!(locale, "no-characters")).await?;
         return Ok(());
     }
 
@@ -34,7 +34,7 @@ pub async fn gubbar(ctx: Context<'_>) -> Result<()> {
         let embed = CreateEmbed::default()
             .title(name)
             .description(description)
-            .field("Hälsning", greeting, false)
+            .field(tr!(locale, "greeting"), greeting, false)
             .thumbnail(avatar);
 
         let components = CreateActionRow::Buttons(vec![
@@ -74,7 +74,7 @@ pub async fn gubbar(ctx: Context<'_>) -> Result<()> {
         let embed = CreateEmbed::default()
             .title(name)
             .description(description)
-            .field("Hälsning", greeting, false)
+            .field(tr!(locale, "greeting"), greeting, false)
             .thumbnail(avatar);
 
         press
@@ -1,15 +1,26 @@
 use std::time::Duration;
 
-use poise::{execute_modal_on_component_interaction, CreateReply, Modal};
+use poise::{CreateReply, Modal};
 use serenity::{
-    ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed, ReactionType,
+    ComponentInteractionCollector, CreateActionRow, CreateAttachment, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateModal, Mentionable, ReactionType,
 };
 
 use crate::{
-    character::{Avatar, Emoji},
+    buttons::{
+        execute_localized_modal, modal_input, modal_paragraph, LocalizedModal, MODAL_LENGTH,
+    },
+    card::{format_example_blocks, is_png, parse_example_blocks, CardData, BLANK_PNG},
+    character::{is_valid_name, Avatar, Emoji},
+    error::Error,
+    ownership::Manager,
     prelude::*,
 };
 
+/// The largest card, or card picture, that is downloaded.
+const MAX_DOWNLOAD_LENGTH: usize = 25 * 1024 * 1024;
+const DOWNLOAD_HOSTS: [&str; 2] = ["cdn.discordapp.com", "media.discordapp.net"];
+
 #[derive(Debug, Clone, Modal)]
 #[name = "Skapa en gubbe"]
 struct CreateCharacterModal {
@@ -30,11 +41,108 @@ struct CreateCharacterModal {
     avatar: Option<String>,
 }
 
+impl LocalizedModal for CreateCharacterModal {
+    fn create_localized(
+        _: Option<Self>,
+        custom_id: String,
+        locale: &str,
+    ) -> CreateInteractionResponse<'static> {
+        let modal =
+            CreateModal::new(custom_id, tr!(locale, "create-character-modal")).components(vec![
+                modal_input(
+                    "name",
+                    tr!(locale, "create-character-name"),
+                    tr!(locale, "create-character-name-placeholder"),
+                    None,
+                    true,
+                ),
+                modal_input(
+                    "greeting",
+                    tr!(locale, "create-character-greeting"),
+                    tr!(locale, "create-character-greeting-placeholder"),
+                    None,
+                    false,
+                ),
+                modal_input(
+                    "description",
+                    tr!(locale, "create-character-description"),
+                    tr!(locale, "create-character-description-placeholder"),
+                    None,
+                    false,
+                ),
+                modal_input(
+                    "emoji",
+                    tr!(locale, "create-character-emoji"),
+                    tr!(locale, "create-character-emoji-placeholder"),
+                    None,
+                    false,
+                ),
+                modal_input(
+                    "avatar",
+                    tr!(locale, "create-character-avatar"),
+                    tr!(locale, "create-character-avatar-placeholder"),
+                    None,
+                    false,
+                ),
+            ]);
+        CreateInteractionResponse::Modal(modal)
+    }
+}
+
+#[derive(Debug, Clone, Modal)]
+#[name = "Exempeldialog"]
+struct ExampleMessagesModal {
+    #[name = "Exempel"]
+    #[placeholder = "<START>…"]
+    #[paragraph]
+    examples: Option<String>,
+}
+
+impl LocalizedModal for ExampleMessagesModal {
+    fn create_localized(
+        defaults: Option<Self>,
+        custom_id: String,
+        locale: &str,
+    ) -> CreateInteractionResponse<'static> {
+        let examples = defaults.and_then(|defaults| defaults.examples);
+        let modal =
+            CreateModal::new(custom_id, tr!(locale, "example-messages-modal")).components(vec![
+                modal_paragraph(
+                    "examples",
+                    tr!(locale, "example-messages-label"),
+                    tr!(locale, "example-messages-placeholder"),
+                    examples,
+                    false,
+                ),
+            ]);
+        CreateInteractionResponse::Modal(modal)
+    }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+enum CardFormat {
+    #[name = "PNG"]
+    Png,
+    #[name = "JSON"]
+    Json,
+}
+
 #[poise::command(
     slash_command,
     prefix_command,
     subcommand_required,
-    subcommands("visa", "skapa", "ändra", "döda")
+    subcommands(
+        "visa",
+        "skapa",
+        "ändra",
+        "exempel",
+        "döda",
+        "överlåt",
+        "publicera",
+        "kopiera",
+        "importera",
+        "exportera"
+    )
 )]
 #[allow(clippy::unused_async)]
 pub async fn gubbe(_: Context<'_>) -> Result<()> {
@@ -48,28 +156,40 @@ async fn visa(
     #[autocomplete = "autocomplete_character_name"]
     namn: String,
 ) -> Result<()> {
-    let Some(most_similar_name) = most_similar_name_to(&namn, ctx) else {
-        ctx.say("Gubben hittades inte!").await?;
+    let locale = locale(ctx);
+    let Some(most_similar_name) = most_similar_name_to(&namn, ctx)? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
         return Ok(());
     };
-    let Some(character) = ctx.data().character(&most_similar_name) else {
-        ctx.say("Gubben hittades inte!").await?;
+    let Some(character) = ctx.data().character(&most_similar_name, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
         return Ok(());
     };
 
+    let stored_avatar = ctx.data().avatar(&character)?;
     let reply = {
         let character_name = character.to_string();
         let greeting = character.greeting.to_string();
         let description = character.description.to_string();
-        let avatar = character.avatar.to_string();
+        let avatar = match stored_avatar {
+            Some(_) => "attachment://avatar.png".to_string(),
+            None => character.avatar.to_string(),
+        };
 
-        let embed = CreateEmbed::default()
+        let mut embed = CreateEmbed::default()
             .title(character_name)
             .description(description)
-            .field("Hälsning", greeting, false)
+            .field(tr!(locale, "greeting"), greeting, false)
             .thumbnail(avatar);
+        if let Some(owner) = character.owner {
+            embed = embed.field(tr!(locale, "owner"), owner.mention().to_string(), true);
+        }
 
-        CreateReply::default().embed(embed)
+        let mut reply = CreateReply::default().embed(embed);
+        if let Some(png) = stored_avatar {
+            reply = reply.attachment(CreateAttachment::bytes(png, "avatar.png"));
+        }
+        reply
     };
 
     ctx.send(reply).await?;
@@ -77,7 +197,7 @@ async fn visa(
     Ok(())
 }
 
-#[poise::command(slash_command, prefix_command)]
+#[poise::command(slash_command, prefix_command, guild_only)]
 async fn skapa(
     ctx: Context<'_>,
     #[description = "Gubbens namn"] namn: Option<String>,
@@ -86,18 +206,27 @@ async fn skapa(
     #[description = "Gubbens emoji"] emoji: Option<String>,
     #[description = "Gubbens profilbild (URL)"] profilbild: Option<String>,
 ) -> Result<()> {
+    let locale = locale(ctx);
     match ctx {
         poise::Context::Application(_) => {
             ctx.defer_ephemeral().await?;
             let Some(namn) = namn else {
-                ctx.say("Hörrudu, jag tror att du glömde ett namn där!")
-                    .await?;
+                ctx.say(tr!(locale, "character-name-missing")).await?;
                 return Ok(());
             };
-            let character = Character::new(namn.clone(), hälsning, beskrivning, emoji, profilbild);
-            ctx.say(format!("Hurra! Gubben {character} skapades."))
-                .await?;
-            ctx.data().insert_character(character);
+            if !may_save(ctx, &namn).await? {
+                return Ok(());
+            }
+            let character = Character::new(namn.clone(), hälsning, beskrivning, emoji, profilbild)
+                .owned_by(ctx.author().id, ctx.guild_id());
+            ctx.say(tr!(
+                locale,
+                "character-created",
+                character = character.to_string()
+            ))
+            .await?;
+            ctx.data().set_avatar(&character, None)?;
+            ctx.data().insert_character(character)?;
         }
         poise::Context::Prefix(_) => {
             let ctx_id = ctx.id();
@@ -106,7 +235,7 @@ async fn skapa(
                     .emoji(ReactionType::try_from("✏️").expect("valid emoji"));
                 let component = CreateActionRow::Buttons(vec![button]);
                 let reply = CreateReply::default()
-                    .content("Var snäll och klicka på nedanstående knapp!")
+                    .content(tr!(locale, "click-the-button"))
                     .components(vec![component])
                     .reply(true);
                 ctx.send(reply).await?;
@@ -120,27 +249,36 @@ async fn skapa(
                     .timeout(Duration::from_secs(60 * 60 * 24))
                     .await
             {
-                let Some(modal) = execute_modal_on_component_interaction::<CreateCharacterModal>(
+                let Some(modal) = execute_localized_modal::<CreateCharacterModal>(
                     ctx.serenity_context(),
-                    interaction,
-                    None,
+                    &interaction,
                     None,
+                    locale,
                 )
                 .await?
                 else {
                     return Ok(());
                 };
                 ctx.defer_ephemeral().await?;
+                if !may_save(ctx, &modal.name).await? {
+                    continue;
+                }
                 let character = Character::new(
                     modal.name,
                     modal.greeting,
                     modal.description,
                     modal.emoji,
                     modal.avatar,
-                );
-                ctx.say(format!("Hurra! Gubben {character} skapades."))
-                    .await?;
-                ctx.data().insert_character(character);
+                )
+                .owned_by(ctx.author().id, ctx.guild_id());
+                ctx.say(tr!(
+                    locale,
+                    "character-created",
+                    character = character.to_string()
+                ))
+                .await?;
+                ctx.data().set_avatar(&character, None)?;
+                ctx.data().insert_character(character)?;
             }
         }
     }
@@ -157,13 +295,34 @@ async fn ändra(
     #[description = "Gubbens beskrivning"] beskrivning: Option<String>,
     #[description = "Gubbens emoji"] emoji: Option<String>,
     #[description = "Gubbens profilbild (URL)"] profilbild: Option<String>,
+    #[description = "Gubbens promptmall, eller \"ingen\" för kanalens mall"]
+    #[autocomplete = "autocomplete_prompt_preset"]
+    mall: Option<String>,
 ) -> Result<()> {
     ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
     let data = ctx.data();
-    let Some(mut character) = data.character(&namn) else {
-        ctx.say("ingen gubbe hittades!").await?;
+    let Some(mut character) = data.character(&namn, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
         return Ok(());
     };
+    if !Manager::from_context(ctx).await.may_manage(&character) {
+        ctx.say(Manager::refusal(locale, &character)).await?;
+        return Ok(());
+    }
+    if let Some(preset_name) = mall {
+        if preset_name.eq_ignore_ascii_case(&tr!(locale, "no-preset-keyword")) {
+            character.prompt_preset = None;
+        } else {
+            let preset = CONFIG.read().prompt_preset_named(&preset_name);
+            let Some(preset) = preset else {
+                ctx.say(tr!(locale, "prompt-preset-not-found", name = preset_name))
+                    .await?;
+                return Ok(());
+            };
+            character.prompt_preset = Some(preset.name);
+        }
+    }
     if let Some(greeting) = hälsning {
         character.greeting = SuperMessage::new_assistant(namn.clone(), greeting);
     };
@@ -175,10 +334,95 @@ async fn ändra(
     }
     if let Some(avatar) = profilbild {
         character.avatar = Avatar::from(avatar);
+        data.set_avatar(&character, None)?;
+    }
+    ctx.say(tr!(
+        locale,
+        "character-changed",
+        character = character.to_string()
+    ))
+    .await?;
+    data.insert_character(character)?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn exempel(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(character) = data.character(&namn, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    if !Manager::from_context(ctx).await.may_manage(&character) {
+        ctx.say(Manager::refusal(locale, &character)).await?;
+        return Ok(());
     }
-    ctx.say(format!("Hurra! Gubben {character} ändrades."))
+    let examples = format_example_blocks(character.examples());
+    if examples.chars().count() > MODAL_LENGTH {
+        ctx.say(tr!(
+            locale,
+            "examples-too-long",
+            character = character.to_string()
+        ))
         .await?;
-    data.insert_character(character);
+        return Ok(());
+    }
+    let ctx_id = ctx.id();
+    {
+        let button = CreateButton::new(ctx_id.to_string())
+            .emoji(ReactionType::try_from("✏️").expect("valid emoji"));
+        let component = CreateActionRow::Buttons(vec![button]);
+        let reply = CreateReply::default()
+            .content(tr!(locale, "click-the-button"))
+            .components(vec![component])
+            .ephemeral(true)
+            .reply(true);
+        ctx.send(reply).await?;
+    }
+    let Some(interaction) =
+        ComponentInteractionCollector::new(ctx.serenity_context().shard.clone())
+            .filter(move |interaction| interaction.data.custom_id.as_str() == ctx_id.to_string())
+            .author_id(ctx.author().id)
+            .timeout(Duration::from_secs(60 * 60))
+            .await
+    else {
+        return Ok(());
+    };
+    let defaults = ExampleMessagesModal {
+        examples: Some(examples).filter(|examples| !examples.is_empty()),
+    };
+    let Some(modal) = execute_localized_modal::<ExampleMessagesModal>(
+        ctx.serenity_context(),
+        &interaction,
+        Some(defaults),
+        locale,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    // The character may have changed while the modal was open.
+    let Some(mut character) = data.character(&character.name.to_string(), ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let blocks = parse_example_blocks(modal.examples.as_deref().unwrap_or_default());
+    let reply = tr!(
+        locale,
+        "examples-changed",
+        character = character.to_string(),
+        blocks = blocks.len()
+    );
+    character.set_examples(blocks);
+    data.insert_character(character)?;
+    ctx.say(reply).await?;
     Ok(())
 }
 
@@ -191,12 +435,314 @@ async fn döda(
     namn: String,
 ) -> Result<()> {
     ctx.defer_ephemeral().await?;
-    let Some(character) = ctx.data().character(&namn) else {
-        ctx.say("ingen gubbe hittades!").await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(character) = data.character(&namn, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    if !Manager::from_context(ctx).await.may_manage(&character) {
+        ctx.say(Manager::refusal(locale, &character)).await?;
+        return Ok(());
+    }
+    data.remove_character(&character)?;
+    ctx.say(tr!(
+        locale,
+        "character-killed",
+        character = character.to_string()
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn överlåt(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    namn: String,
+    #[description = "Gubbens nya ägare"] ägare: serenity::User,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(character) = data.character(&namn, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
         return Ok(());
     };
-    ctx.data().remove_character(&namn);
-    ctx.say(format!("Hurra! Gubben {character} dödades."))
+    if !Manager::from_context(ctx).await.may_manage(&character) {
+        ctx.say(Manager::refusal(locale, &character)).await?;
+        return Ok(());
+    }
+    if ägare.bot() {
+        ctx.say(tr!(locale, "bot-cannot-own")).await?;
+        return Ok(());
+    }
+    let mut character = character;
+    character.owner = Some(ägare.id);
+    ctx.say(tr!(
+        locale,
+        "character-transferred",
+        character = character.to_string(),
+        owner = ägare.mention().to_string()
+    ))
+    .await?;
+    data.insert_character(character)?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn publicera(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(mut character) = data.character(&namn, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    if character.guild.is_none() {
+        ctx.say(tr!(
+            locale,
+            "character-already-public",
+            character = character.to_string()
+        ))
+        .await?;
+        return Ok(());
+    }
+    if !Manager::from_context(ctx).await.may_manage(&character) {
+        ctx.say(Manager::refusal(locale, &character)).await?;
+        return Ok(());
+    }
+    let from = character.guild.take();
+    if !data.move_character(&character, from)? {
+        ctx.say(tr!(
+            locale,
+            "public-character-exists",
+            name = character.name.to_string()
+        ))
+        .await?;
+        return Ok(());
+    }
+    ctx.say(tr!(
+        locale,
+        "character-published",
+        character = character.to_string()
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn kopiera(
+    ctx: Context<'_>,
+    #[description = "Den offentliga gubbens namn"]
+    #[autocomplete = "autocomplete_public_character_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(guild_id) = ctx.guild_id() else {
+        ctx.say(tr!(locale, "copy-needs-server")).await?;
+        return Ok(());
+    };
+    let Some(character) = data.storage.character(&namn, None)? else {
+        ctx.say(tr!(locale, "public-character-not-found")).await?;
+        return Ok(());
+    };
+    if data
+        .storage
+        .character(&character.name.to_string(), Some(guild_id))?
+        .is_some()
+    {
+        ctx.say(tr!(
+            locale,
+            "server-character-exists",
+            name = character.name.to_string()
+        ))
         .await?;
+        return Ok(());
+    }
+    let avatar = data.avatar(&character)?;
+    let character = character.owned_by(ctx.author().id, Some(guild_id));
+    ctx.say(tr!(
+        locale,
+        "character-copied",
+        character = character.to_string()
+    ))
+    .await?;
+    data.set_avatar(&character, avatar.as_deref())?;
+    data.insert_character(character)?;
     Ok(())
 }
+
+#[poise::command(slash_command, guild_only)]
+async fn importera(
+    ctx: Context<'_>,
+    #[description = "Gubbens kort (PNG eller JSON)"] kort: serenity::Attachment,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    if !usize::try_from(kort.size).is_ok_and(|size| size <= MAX_DOWNLOAD_LENGTH) {
+        ctx.say(tr!(
+            locale,
+            "card-too-large",
+            megabytes = MAX_DOWNLOAD_LENGTH / 1024 / 1024
+        ))
+        .await?;
+        return Ok(());
+    }
+    let bytes = kort.download().await?;
+    let card = match CardData::from_bytes(&bytes) {
+        Ok(card) => card,
+        Err(why) => {
+            ctx.say(tr!(locale, "card-unreadable", error = why.to_string()))
+                .await?;
+            return Ok(());
+        }
+    };
+    if card.name.trim().is_empty() {
+        ctx.say(tr!(locale, "card-name-missing")).await?;
+        return Ok(());
+    }
+    let data = ctx.data();
+    let name = card.name.trim().to_string();
+    if !is_valid_name(&name) {
+        ctx.say(tr!(locale, "character-name-invalid")).await?;
+        return Ok(());
+    }
+    if data.storage.character(&name, ctx.guild_id())?.is_some() {
+        ctx.say(tr!(locale, "imported-character-exists", name = name))
+            .await?;
+        return Ok(());
+    }
+    let imported = card.into_character(None, locale);
+    let character = imported.character.owned_by(ctx.author().id, ctx.guild_id());
+    let reply = tr!(
+        locale,
+        "character-imported",
+        character = character.to_string(),
+        unmapped = imported.unmapped_fields.join(", "),
+        unmapped_count = imported.unmapped_fields.len()
+    );
+    // Discord's attachment links expire, so the card's picture is kept as is.
+    if is_png(&bytes) {
+        data.set_avatar(&character, Some(&bytes))?;
+    }
+    data.insert_character(character)?;
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn exportera(
+    ctx: Context<'_>,
+    #[description = "Gubbens namn"]
+    #[autocomplete = "autocomplete_character_name"]
+    namn: String,
+    #[description = "Filformat (PNG som standard)"] format: Option<CardFormat>,
+) -> Result<()> {
+    ctx.defer().await?;
+    let locale = locale(ctx);
+    let Some(most_similar_name) = most_similar_name_to(&namn, ctx)? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let Some(character) = ctx.data().character(&most_similar_name, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "character-not-found")).await?;
+        return Ok(());
+    };
+    let file_name = character.name.to_string();
+    let avatar = character.avatar.to_string();
+    let stored_avatar = ctx.data().avatar(&character)?;
+    let card = CardData::from(character.clone());
+    let attachment = match format.unwrap_or(CardFormat::Png) {
+        CardFormat::Json => {
+            CreateAttachment::bytes(card.to_v2_json()?, format!("{file_name}.json"))
+        }
+        CardFormat::Png => {
+            let base_png = match stored_avatar {
+                Some(bytes) => bytes,
+                None => match download(&avatar).await {
+                    Ok(bytes) => bytes,
+                    Err(why) => {
+                        tracing::warn!("could not download avatar! {why}");
+                        Vec::new()
+                    }
+                },
+            };
+            let base_png = if is_png(&base_png) {
+                base_png
+            } else {
+                BLANK_PNG.to_vec()
+            };
+            CreateAttachment::bytes(card.to_png(&base_png)?, format!("{file_name}.png"))
+        }
+    };
+    let reply = CreateReply::default()
+        .content(tr!(
+            locale,
+            "character-exported",
+            character = character.to_string()
+        ))
+        .attachment(attachment);
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+async fn may_save(ctx: Context<'_>, character_name: &str) -> Result<bool> {
+    if !is_valid_name(character_name) {
+        ctx.say(tr!(locale(ctx), "character-name-invalid")).await?;
+        return Ok(false);
+    }
+    let Some(existing) = ctx
+        .data()
+        .storage
+        .character(character_name, ctx.guild_id())?
+    else {
+        return Ok(true);
+    };
+    if Manager::from_context(ctx).await.may_manage(&existing) {
+        return Ok(true);
+    }
+    ctx.say(tr!(
+        locale(ctx),
+        "character-belongs-to-someone-else",
+        character = existing.to_string()
+    ))
+    .await?;
+    Ok(false)
+}
+
+/// Downloads `url` from Discord's CDN, treating an error status as a failed
+/// download. Avatars are links anyone can set, so nothing else is fetched, and
+/// nothing larger than [`MAX_DOWNLOAD_LENGTH`].
+async fn download(url: &str) -> Result<Vec<u8>> {
+    let url = reqwest::Url::parse(url).map_err(|_| Error::ForeignDownload)?;
+    if url.scheme() != "https"
+        || !url
+            .host_str()
+            .is_some_and(|host| DOWNLOAD_HOSTS.contains(&host))
+    {
+        return Err(Error::ForeignDownload);
+    }
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > MAX_DOWNLOAD_LENGTH {
+            return Err(Error::DownloadTooLarge(MAX_DOWNLOAD_LENGTH));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
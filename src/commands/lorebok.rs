@@ -0,0 +1,401 @@
+use crate::{
+    lorebook::{split_keys, InsertionPosition, LoreEntry, Lorebook, LorebookRef},
+    ownership::Manager,
+    prelude::*,
+};
+use itertools::Itertools;
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+const PREVIEW_LENGTH: usize = 100;
+const EMBED_DESCRIPTION_LENGTH: usize = 4096;
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands(
+        "skapa",
+        "lista",
+        "ändra",
+        "radera",
+        "släng",
+        "importera",
+        "koppla",
+        "lossa"
+    )
+)]
+#[allow(clippy::unused_async)]
+pub async fn lorebok(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+async fn skapa(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    bok: String,
+    #[description = "Nyckelord, separerade med kommatecken"] nycklar: String,
+    #[description = "Inläggets innehåll"] innehåll: String,
+    #[description = "Sekundära nyckelord, separerade med kommatecken"] sekundära_nycklar: Option<
+        String,
+    >,
+    #[description = "Var inlägget ska placeras"] position: Option<InsertionPosition>,
+    #[description = "Prioritet (högre prioritet placeras först)"] prioritet: Option<i64>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let mut lorebook = match data.storage.lorebook(&bok, ctx.guild_id())? {
+        Some(lorebook) => {
+            if !Manager::from_context(ctx).await.may_manage(&lorebook) {
+                ctx.say(refusal(locale, &lorebook)).await?;
+                return Ok(());
+            }
+            lorebook
+        }
+        None => Lorebook::new(&bok).owned_by(ctx.author().id, ctx.guild_id()),
+    };
+    let mut entry = LoreEntry::new(&nycklar, innehåll);
+    if let Some(secondary_keys) = sekundära_nycklar {
+        entry.secondary_keys = split_keys(&secondary_keys);
+    }
+    if let Some(position) = position {
+        entry.position = position;
+    }
+    if let Some(priority) = prioritet {
+        entry.priority = priority;
+    }
+    lorebook.entries.push(entry);
+    data.insert_lorebook(&lorebook)?;
+    ctx.say(tr!(
+        locale,
+        "lore-entry-added",
+        entry = lorebook.entries.len(),
+        lorebook = bok
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lista(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    #[rest]
+    bok: String,
+) -> Result<()> {
+    let locale = locale(ctx);
+    let Some(lorebook) = ctx.data().lorebook(&bok, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "lorebook-not-found")).await?;
+        return Ok(());
+    };
+    if lorebook.entries.is_empty() {
+        ctx.say(tr!(locale, "lorebook-empty")).await?;
+        return Ok(());
+    }
+
+    let mut description = lorebook
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let preview = entry
+                .content
+                .chars()
+                .take(PREVIEW_LENGTH)
+                .collect::<String>();
+            tr!(
+                locale,
+                "lore-entry-line",
+                number = index + 1,
+                keys = entry.keys.join(", "),
+                enabled = entry.enabled.to_string(),
+                preview = preview,
+            )
+        })
+        .join("\n\n");
+    if description.chars().count() > EMBED_DESCRIPTION_LENGTH {
+        description = description
+            .chars()
+            .take(EMBED_DESCRIPTION_LENGTH - 1)
+            .chain(['…'])
+            .collect();
+    }
+    let embed = CreateEmbed::default()
+        .title(lorebook.name)
+        .description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+#[allow(clippy::too_many_arguments)]
+async fn ändra(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    bok: String,
+    #[description = "Inläggets nummer"] nummer: usize,
+    #[description = "Nyckelord, separerade med kommatecken"] nycklar: Option<String>,
+    #[description = "Inläggets innehåll"] innehåll: Option<String>,
+    #[description = "Sekundära nyckelord, separerade med kommatecken"] sekundära_nycklar: Option<
+        String,
+    >,
+    #[description = "Var inlägget ska placeras"] position: Option<InsertionPosition>,
+    #[description = "Prioritet (högre prioritet placeras först)"] prioritet: Option<i64>,
+    #[description = "Om inlägget är påslaget"] påslaget: Option<bool>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(mut lorebook) = managed_lorebook(ctx, &bok).await? else {
+        return Ok(());
+    };
+    let Some(entry) = nummer
+        .checked_sub(1)
+        .and_then(|index| lorebook.entries.get_mut(index))
+    else {
+        ctx.say(tr!(locale, "lore-entry-not-found")).await?;
+        return Ok(());
+    };
+    if let Some(keys) = nycklar {
+        entry.keys = split_keys(&keys);
+    }
+    if let Some(content) = innehåll {
+        entry.content = content;
+    }
+    if let Some(secondary_keys) = sekundära_nycklar {
+        entry.secondary_keys = split_keys(&secondary_keys);
+    }
+    if let Some(position) = position {
+        entry.position = position;
+    }
+    if let Some(priority) = prioritet {
+        entry.priority = priority;
+    }
+    if let Some(enabled) = påslaget {
+        entry.enabled = enabled;
+    }
+    data.insert_lorebook(&lorebook)?;
+    ctx.say(tr!(
+        locale,
+        "lore-entry-changed",
+        entry = nummer,
+        lorebook = bok
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn radera(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    bok: String,
+    #[description = "Inläggets nummer"] nummer: usize,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(mut lorebook) = managed_lorebook(ctx, &bok).await? else {
+        return Ok(());
+    };
+    let Some(index) = nummer
+        .checked_sub(1)
+        .filter(|index| *index < lorebook.entries.len())
+    else {
+        ctx.say(tr!(locale, "lore-entry-not-found")).await?;
+        return Ok(());
+    };
+    lorebook.entries.remove(index);
+    data.insert_lorebook(&lorebook)?;
+    ctx.say(tr!(
+        locale,
+        "lore-entry-deleted",
+        entry = nummer,
+        lorebook = bok
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn släng(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    #[rest]
+    bok: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let Some(lorebook) = managed_lorebook(ctx, &bok).await? else {
+        return Ok(());
+    };
+    ctx.data().remove_lorebook(&lorebook)?;
+    ctx.say(tr!(locale, "lorebook-deleted", lorebook = lorebook.name))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+async fn importera(
+    ctx: Context<'_>,
+    #[description = "SillyTavern World Info (JSON)"] fil: serenity::Attachment,
+    #[description = "Lorebokens namn"] namn: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let name = namn.unwrap_or_else(|| fil.filename.trim_end_matches(".json").to_string());
+    let data = ctx.data();
+    if data.storage.lorebook(&name, ctx.guild_id())?.is_some() {
+        ctx.say(tr!(locale, "imported-lorebook-exists", lorebook = name))
+            .await?;
+        return Ok(());
+    }
+    let bytes = fil.download().await?;
+    let lorebook = match Lorebook::from_world_info(&name, &bytes) {
+        Ok(lorebook) => lorebook.owned_by(ctx.author().id, ctx.guild_id()),
+        Err(why) => {
+            ctx.say(tr!(locale, "file-unreadable", error = why.to_string()))
+                .await?;
+            return Ok(());
+        }
+    };
+    data.insert_lorebook(&lorebook)?;
+    ctx.say(tr!(
+        locale,
+        "lorebook-imported",
+        lorebook = name,
+        entries = lorebook.entries.len()
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn koppla(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    bok: String,
+    #[description = "Gubbens namn (annars kopplas loreboken till kanalen)"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let Some(lorebook) = data.lorebook(&bok, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "lorebook-not-found")).await?;
+        return Ok(());
+    };
+    if let Some(character_name) = gubbe {
+        let Some(mut character) = data.character(&character_name, ctx.guild_id())? else {
+            ctx.say(tr!(locale, "character-not-found")).await?;
+            return Ok(());
+        };
+        if !Manager::from_context(ctx).await.may_manage(&character) {
+            ctx.say(Manager::refusal(locale, &character)).await?;
+            return Ok(());
+        }
+        // The character looks its lorebooks up in its own scope.
+        if data.lorebook(&bok, character.guild)?.is_none() {
+            ctx.say(tr!(locale, "lorebook-not-found")).await?;
+            return Ok(());
+        }
+        if !character.lorebooks.contains(&bok) {
+            character.lorebooks.push(bok.clone());
+        }
+        ctx.say(tr!(
+            locale,
+            "lorebook-attached",
+            lorebook = bok,
+            character = character.to_string()
+        ))
+        .await?;
+        data.insert_character(character)?;
+    } else {
+        let mut lorebooks = data.channel_lorebooks(ctx.channel_id())?;
+        let attached = LorebookRef::from(&lorebook);
+        if !lorebooks.contains(&attached) {
+            lorebooks.push(attached);
+        }
+        data.set_channel_lorebooks(ctx.channel_id(), &lorebooks)?;
+        ctx.say(tr!(locale, "lorebook-attached-to-channel", lorebook = bok))
+            .await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lossa(
+    ctx: Context<'_>,
+    #[description = "Lorebokens namn"]
+    #[autocomplete = "autocomplete_lorebook_name"]
+    bok: String,
+    #[description = "Gubbens namn (annars lossas loreboken från kanalen)"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    if let Some(character_name) = gubbe {
+        let Some(mut character) = data.character(&character_name, ctx.guild_id())? else {
+            ctx.say(tr!(locale, "character-not-found")).await?;
+            return Ok(());
+        };
+        if !Manager::from_context(ctx).await.may_manage(&character) {
+            ctx.say(Manager::refusal(locale, &character)).await?;
+            return Ok(());
+        }
+        character
+            .lorebooks
+            .retain(|lorebook_name| *lorebook_name != bok);
+        ctx.say(tr!(
+            locale,
+            "lorebook-detached",
+            lorebook = bok,
+            character = character.to_string()
+        ))
+        .await?;
+        data.insert_character(character)?;
+    } else {
+        let mut lorebooks = data.channel_lorebooks(ctx.channel_id())?;
+        lorebooks.retain(|lorebook| lorebook.name != bok);
+        data.set_channel_lorebooks(ctx.channel_id(), &lorebooks)?;
+        ctx.say(tr!(
+            locale,
+            "lorebook-detached-from-channel",
+            lorebook = bok
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// The lorebook called `name`, if the author may change it.
+async fn managed_lorebook(ctx: Context<'_>, name: &str) -> Result<Option<Lorebook>> {
+    let locale = locale(ctx);
+    let Some(lorebook) = ctx.data().lorebook(name, ctx.guild_id())? else {
+        ctx.say(tr!(locale, "lorebook-not-found")).await?;
+        return Ok(None);
+    };
+    if !Manager::from_context(ctx).await.may_manage(&lorebook) {
+        ctx.say(refusal(locale, &lorebook)).await?;
+        return Ok(None);
+    }
+    Ok(Some(lorebook))
+}
+
+fn refusal(locale: &str, lorebook: &Lorebook) -> String {
+    tr!(
+        locale,
+        "lorebook-not-allowed",
+        lorebook = lorebook.name.clone()
+    )
+}
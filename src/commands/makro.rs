@@ -0,0 +1,136 @@
+use crate::{
+    macros::{is_valid_name, CustomMacro},
+    ownership::Manager,
+    prelude::*,
+};
+use itertools::Itertools;
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands("skapa", "lista", "radera")
+)]
+#[allow(clippy::unused_async)]
+pub async fn makro(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn skapa(
+    ctx: Context<'_>,
+    #[description = "Makrots namn, som används som {{namn}}"] namn: String,
+    #[description = "Texten som makrot ersätts med"]
+    #[rest]
+    text: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    if !may_change_macros(ctx).await {
+        ctx.say(tr!(locale, "macros-not-allowed")).await?;
+        return Ok(());
+    }
+    let name = namn.trim().trim_start_matches("{{").trim_end_matches("}}");
+    if !is_valid_name(name) {
+        ctx.say(tr!(locale, "macro-name-invalid", name = name))
+            .await?;
+        return Ok(());
+    }
+    let data = ctx.data();
+    let mut macros = data.channel_macros(ctx.channel_id())?;
+    let custom_macro = CustomMacro {
+        name: name.to_string(),
+        text,
+    };
+    let existing = macros
+        .iter_mut()
+        .find(|existing| existing.name.eq_ignore_ascii_case(name));
+    let replaced = existing.is_some();
+    match existing {
+        Some(existing) => *existing = custom_macro,
+        None => macros.push(custom_macro),
+    }
+    data.set_channel_macros(ctx.channel_id(), &macros)?;
+    if replaced {
+        ctx.say(tr!(locale, "macro-updated", name = name)).await?;
+    } else {
+        ctx.say(tr!(locale, "macro-created", name = name)).await?;
+    }
+    Ok(())
+}
+
+/// Channel macros reach everyone's prompts, so in a server only its
+/// administrators and the bot's administrators may change them.
+async fn may_change_macros(ctx: Context<'_>) -> bool {
+    if ctx.guild_id().is_none() {
+        return true;
+    }
+    let manager = Manager::from_context(ctx).await;
+    manager.guild_admin || manager.bot_admin
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lista(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let channel_macros = ctx.data().channel_macros(ctx.channel_id())?;
+    let config_macros = CONFIG.read().macros();
+    if channel_macros.is_empty() && config_macros.is_empty() {
+        ctx.say(tr!(locale, "no-macros")).await?;
+        return Ok(());
+    }
+    let description = channel_macros
+        .iter()
+        .map(|custom_macro| (custom_macro, true))
+        .chain(
+            config_macros
+                .iter()
+                .map(|custom_macro| (custom_macro, false)),
+        )
+        .map(|(custom_macro, channel)| {
+            tr!(
+                locale,
+                "macro-line",
+                name = custom_macro.name.clone(),
+                text = custom_macro.text.clone(),
+                channel = channel.to_string(),
+            )
+        })
+        .join("\n\n");
+    let embed = CreateEmbed::default()
+        .title(tr!(locale, "macros-title"))
+        .description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn radera(
+    ctx: Context<'_>,
+    #[description = "Makrots namn"]
+    #[autocomplete = "autocomplete_macro_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    if !may_change_macros(ctx).await {
+        ctx.say(tr!(locale, "macros-not-allowed")).await?;
+        return Ok(());
+    }
+    let data = ctx.data();
+    let mut macros = data.channel_macros(ctx.channel_id())?;
+    let Some(index) = macros
+        .iter()
+        .position(|custom_macro| custom_macro.name.eq_ignore_ascii_case(namn.trim()))
+    else {
+        ctx.say(tr!(locale, "macro-not-found", name = namn)).await?;
+        return Ok(());
+    };
+    let custom_macro = macros.remove(index);
+    data.set_channel_macros(ctx.channel_id(), &macros)?;
+    ctx.say(tr!(locale, "macro-deleted", name = custom_macro.name))
+        .await?;
+    Ok(())
+}
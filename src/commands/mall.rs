@@ -0,0 +1,88 @@
+use crate::{ownership::Manager, prelude::*};
+use itertools::Itertools;
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands("lista", "kanal")
+)]
+#[allow(clippy::unused_async)]
+pub async fn mall(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lista(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let channel_preset = ctx.data().channel_prompt_preset(ctx.channel_id())?;
+    let (default_preset, preset_names) = {
+        let config = CONFIG.read();
+        (
+            config.prompt_preset(None).name,
+            config.prompt_preset_names(),
+        )
+    };
+    let description = preset_names
+        .iter()
+        .map(|name| {
+            tr!(
+                locale,
+                "prompt-preset-line",
+                name = name.clone(),
+                default = name.eq_ignore_ascii_case(&default_preset).to_string(),
+                channel = channel_preset
+                    .as_ref()
+                    .is_some_and(|preset| name.eq_ignore_ascii_case(preset))
+                    .to_string(),
+            )
+        })
+        .join("\n");
+    let embed = CreateEmbed::default()
+        .title(tr!(locale, "prompt-presets-title"))
+        .description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn kanal(
+    ctx: Context<'_>,
+    #[description = "Promptmallen för den här kanalen, tomt för standardmallen"]
+    #[autocomplete = "autocomplete_prompt_preset"]
+    #[rest]
+    namn: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    if ctx.guild_id().is_some() {
+        let manager = Manager::from_context(ctx).await;
+        if !manager.guild_admin && !manager.bot_admin {
+            ctx.say(tr!(locale, "channel-prompt-preset-not-allowed"))
+                .await?;
+            return Ok(());
+        }
+    }
+    let data = ctx.data();
+    let Some(namn) = namn else {
+        data.set_channel_prompt_preset(ctx.channel_id(), None)?;
+        ctx.say(tr!(locale, "channel-prompt-preset-reset")).await?;
+        return Ok(());
+    };
+    let preset = CONFIG.read().prompt_preset_named(&namn);
+    let Some(preset) = preset else {
+        ctx.say(tr!(locale, "prompt-preset-not-found", name = namn))
+            .await?;
+        return Ok(());
+    };
+    data.set_channel_prompt_preset(ctx.channel_id(), Some(&preset.name))?;
+    ctx.say(tr!(
+        locale,
+        "channel-prompt-preset-changed",
+        name = preset.name
+    ))
+    .await?;
+    Ok(())
+}
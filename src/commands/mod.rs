@@ -1,3 +1,13 @@
 pub mod chat;
+pub mod fortsatt;
+pub mod grenar;
+pub mod grupp;
 pub mod gubbar;
 pub mod gubbe;
+pub mod lorebok;
+pub mod makro;
+pub mod mall;
+pub mod persona;
+pub mod sammanfatta;
+pub mod sampler;
+pub mod sprak;
@@ -0,0 +1,144 @@
+use crate::{persona::Persona, prelude::*};
+use itertools::Itertools;
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands("skapa", "lista", "använd", "sluta", "radera")
+)]
+#[allow(clippy::unused_async)]
+pub async fn persona(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+async fn skapa(
+    ctx: Context<'_>,
+    #[description = "Personans namn"] namn: String,
+    #[description = "Vem du är i rollspelet"] beskrivning: String,
+    #[description = "Länk till personans profilbild"] profilbild: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let user_id = ctx.author().id;
+    let mut personas = data.personas(user_id)?;
+    let mut persona = Persona::new(namn.trim(), beskrivning);
+    persona.avatar = profilbild;
+    let name = persona.name.clone();
+    let replaced = personas.insert(persona);
+    data.set_personas(user_id, &personas)?;
+    if replaced {
+        ctx.say(tr!(locale, "persona-updated", name = name)).await?;
+    } else {
+        ctx.say(tr!(locale, "persona-created", name = name)).await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn lista(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let personas = ctx.data().personas(ctx.author().id)?;
+    if personas.personas.is_empty() {
+        ctx.say(tr!(locale, "no-personas")).await?;
+        return Ok(());
+    }
+    let active = personas.active_in(ctx.channel_id());
+    let description = personas
+        .personas
+        .iter()
+        .map(|persona| {
+            tr!(
+                locale,
+                "persona-line",
+                name = persona.name.clone(),
+                description = persona.description.clone(),
+                active = (active == Some(persona)).to_string(),
+            )
+        })
+        .join("\n\n");
+    let mut embed = CreateEmbed::default()
+        .title(tr!(locale, "personas-title"))
+        .description(description);
+    if let Some(avatar) = active.and_then(|persona| persona.avatar.clone()) {
+        embed = embed.thumbnail(avatar);
+    }
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn använd(
+    ctx: Context<'_>,
+    #[description = "Personans namn"]
+    #[autocomplete = "autocomplete_persona_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let user_id = ctx.author().id;
+    let mut personas = data.personas(user_id)?;
+    let Some(persona) = personas.activate(ctx.channel_id(), &namn) else {
+        ctx.say(tr!(locale, "persona-not-found", name = namn))
+            .await?;
+        return Ok(());
+    };
+    let mut embed = CreateEmbed::default().description(tr!(
+        locale,
+        "persona-activated",
+        name = persona.name.clone()
+    ));
+    if let Some(avatar) = persona.avatar.clone() {
+        embed = embed.thumbnail(avatar);
+    }
+    data.set_personas(user_id, &personas)?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn sluta(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let user_id = ctx.author().id;
+    let mut personas = data.personas(user_id)?;
+    let Some(name) = personas.deactivate(ctx.channel_id()) else {
+        ctx.say(tr!(locale, "no-active-persona")).await?;
+        return Ok(());
+    };
+    data.set_personas(user_id, &personas)?;
+    ctx.say(tr!(locale, "persona-deactivated", name = name))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn radera(
+    ctx: Context<'_>,
+    #[description = "Personans namn"]
+    #[autocomplete = "autocomplete_persona_name"]
+    #[rest]
+    namn: String,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let user_id = ctx.author().id;
+    let mut personas = data.personas(user_id)?;
+    let Some(persona) = personas.remove(&namn) else {
+        ctx.say(tr!(locale, "persona-not-found", name = namn))
+            .await?;
+        return Ok(());
+    };
+    data.set_personas(user_id, &personas)?;
+    ctx.say(tr!(locale, "persona-deleted", name = persona.name))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::prelude::*;
+use poise::{
+    serenity_prelude::{CreateEmbed, CreateEmbedFooter, GetMessages},
+    CreateReply,
+};
+
+#[poise::command(slash_command, prefix_command)]
+pub async fn sammanfatta(
+    ctx: Context<'_>,
+    #[description = "Ny sammanfattning"]
+    #[rest]
+    sammanfattning: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let messages = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().limit(100))
+        .await?;
+    let Some(mut history) = ctx.data().latest_history(&messages)? else {
+        ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+        return Ok(());
+    };
+
+    if let Some(summary) = sammanfattning {
+        history.set_summary(summary);
+        ctx.data().insert_history(history)?;
+        ctx.say(tr!(locale, "summary-changed")).await?;
+        return Ok(());
+    }
+
+    let Some(summary) = history.summary else {
+        ctx.say(tr!(locale, "no-summary")).await?;
+        return Ok(());
+    };
+    let embed = CreateEmbed::default()
+        .title(history.character.to_string())
+        .description(summary.text)
+        .footer(CreateEmbedFooter::new(tr!(
+            locale,
+            "summary-footer",
+            turns = summary.covered_turns
+        )));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
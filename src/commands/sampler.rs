@@ -0,0 +1,275 @@
+use crate::{
+    ownership::Manager,
+    prelude::*,
+    sampler::{self, parse_logit_bias, parse_stop, SamplerSetting, SamplerSettings},
+};
+use itertools::Itertools;
+use poise::{
+    serenity_prelude::{CreateEmbed, GetMessages},
+    CreateReply,
+};
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommand_required,
+    subcommands("visa", "ändra", "återställ", "förinställningar")
+)]
+#[allow(clippy::unused_async)]
+pub async fn sampler(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn visa(
+    ctx: Context<'_>,
+    #[description = "Gubben att visa, annars chatten i den här kanalen"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    gubbe: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let (title, overrides, effective) = if let Some(gubbe) = gubbe {
+        let Some(character) = data.character(&gubbe, ctx.guild_id())? else {
+            ctx.say(tr!(locale, "named-character-not-found", name = gubbe))
+                .await?;
+            return Ok(());
+        };
+        let effective = sampler::resolve(&character.sampler, &SamplerSettings::default());
+        (
+            tr!(
+                locale,
+                "sampler-title-character",
+                character = character.to_string()
+            ),
+            character.sampler,
+            effective,
+        )
+    } else {
+        let Some(history) = latest_chat(ctx).await? else {
+            ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+            return Ok(());
+        };
+        let effective = data.sampler_for(&history)?;
+        (
+            tr!(locale, "sampler-title-chat"),
+            history.sampler,
+            effective,
+        )
+    };
+    let embed = CreateEmbed::default()
+        .title(title)
+        .field(
+            tr!(locale, "sampler-overrides"),
+            code_block(locale, &overrides),
+            false,
+        )
+        .field(
+            tr!(locale, "sampler-effective"),
+            code_block(locale, &effective),
+            false,
+        );
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+#[allow(clippy::too_many_arguments)]
+async fn ändra(
+    ctx: Context<'_>,
+    #[description = "Gubben att ändra, annars chatten i den här kanalen"]
+    #[autocomplete = "autocomplete_character_name"]
+    gubbe: Option<String>,
+    #[description = "Förinställning att utgå från"]
+    #[autocomplete = "autocomplete_sampler_preset"]
+    förinställning: Option<String>,
+    #[description = "Temperatur"]
+    #[min = 0]
+    temperatur: Option<f32>,
+    #[description = "Top P"]
+    #[min = 0]
+    #[max = 1]
+    top_p: Option<f32>,
+    #[description = "Top K"] top_k: Option<u32>,
+    #[description = "Min P"]
+    #[min = 0]
+    #[max = 1]
+    min_p: Option<f32>,
+    #[description = "Upprepningsstraff"] upprepningsstraff: Option<f32>,
+    #[description = "Frekvensstraff"] frekvensstraff: Option<f32>,
+    #[description = "Närvarostraff"] närvarostraff: Option<f32>,
+    #[description = "Högsta antal tokens per svar"]
+    #[min = 1]
+    max_tokens: Option<u16>,
+    #[description = "Stoppsekvenser, separerade med |"] stopp: Option<String>,
+    #[description = "Frö för upprepbara svar"] frö: Option<i64>,
+    #[description = "Logit bias, t.ex. token:styrka"] logit_bias: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    if let Some(preset) = &förinställning {
+        if CONFIG.read().sampler_preset(preset).is_none() {
+            ctx.say(tr!(
+                locale,
+                "sampler-preset-not-found",
+                name = preset.clone()
+            ))
+            .await?;
+            return Ok(());
+        }
+    }
+    let logit_bias = match logit_bias.as_deref().map(parse_logit_bias) {
+        Some(None) => {
+            ctx.say(tr!(locale, "invalid-logit-bias")).await?;
+            return Ok(());
+        }
+        Some(Some(biases)) => Some(biases),
+        None => None,
+    };
+    let changes = SamplerSettings {
+        preset: förinställning,
+        temperature: temperatur,
+        top_p,
+        top_k,
+        min_p,
+        repetition_penalty: upprepningsstraff,
+        frequency_penalty: frekvensstraff,
+        presence_penalty: närvarostraff,
+        max_tokens,
+        stop: stopp.as_deref().map(parse_stop),
+        seed: frö,
+        logit_bias,
+    };
+
+    if let Some(gubbe) = gubbe {
+        let Some(mut character) = data.character(&gubbe, ctx.guild_id())? else {
+            ctx.say(tr!(locale, "named-character-not-found", name = gubbe))
+                .await?;
+            return Ok(());
+        };
+        if !Manager::from_context(ctx).await.may_manage(&character) {
+            ctx.say(Manager::refusal(locale, &character)).await?;
+            return Ok(());
+        }
+        character.sampler = changes.or(&character.sampler);
+        let reply = tr!(
+            locale,
+            "sampler-changed-character",
+            character = character.to_string(),
+            settings = code_block(locale, &character.sampler)
+        );
+        data.insert_character(character)?;
+        ctx.say(reply).await?;
+    } else {
+        let Some(mut history) = latest_chat(ctx).await? else {
+            ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+            return Ok(());
+        };
+        history.sampler = changes.or(&history.sampler);
+        let reply = tr!(
+            locale,
+            "sampler-changed-chat",
+            settings = code_block(locale, &history.sampler)
+        );
+        data.insert_history(history)?;
+        ctx.say(reply).await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn återställ(
+    ctx: Context<'_>,
+    #[description = "Inställningen att återställa, annars alla"] inställning: Option<
+        SamplerSetting,
+    >,
+    #[description = "Gubben att återställa, annars chatten i den här kanalen"]
+    #[autocomplete = "autocomplete_character_name"]
+    #[rest]
+    gubbe: Option<String>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let data = ctx.data();
+    let reset = |settings: &mut SamplerSettings| match inställning {
+        Some(setting) => settings.clear(setting),
+        None => *settings = SamplerSettings::default(),
+    };
+    let setting =
+        inställning.map(|setting| tr!(locale, "sampler-setting", setting = setting.key()));
+    if let Some(gubbe) = gubbe {
+        let Some(mut character) = data.character(&gubbe, ctx.guild_id())? else {
+            ctx.say(tr!(locale, "named-character-not-found", name = gubbe))
+                .await?;
+            return Ok(());
+        };
+        if !Manager::from_context(ctx).await.may_manage(&character) {
+            ctx.say(Manager::refusal(locale, &character)).await?;
+            return Ok(());
+        }
+        reset(&mut character.sampler);
+        let reply = match setting {
+            Some(setting) => tr!(
+                locale,
+                "sampler-cleared-character",
+                character = character.to_string(),
+                setting = setting
+            ),
+            None => tr!(
+                locale,
+                "sampler-reset-character",
+                character = character.to_string()
+            ),
+        };
+        data.insert_character(character)?;
+        ctx.say(reply).await?;
+    } else {
+        let Some(mut history) = latest_chat(ctx).await? else {
+            ctx.say(tr!(locale, "no-chat-in-channel")).await?;
+            return Ok(());
+        };
+        reset(&mut history.sampler);
+        data.insert_history(history)?;
+        let reply = match setting {
+            Some(setting) => tr!(locale, "sampler-cleared-chat", setting = setting),
+            None => tr!(locale, "sampler-reset-chat"),
+        };
+        ctx.say(reply).await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command)]
+async fn förinställningar(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let locale = locale(ctx);
+    let presets = CONFIG.read().sampler_presets();
+    if presets.is_empty() {
+        ctx.say(tr!(locale, "no-sampler-presets")).await?;
+        return Ok(());
+    }
+    let description = presets
+        .iter()
+        .map(|(name, settings)| format!("**{name}**\n{}", code_block(locale, settings)))
+        .join("\n");
+    let embed = CreateEmbed::default()
+        .title(tr!(locale, "sampler-presets-title"))
+        .description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+async fn latest_chat(ctx: Context<'_>) -> Result<Option<History>> {
+    let messages = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().limit(100))
+        .await?;
+    ctx.data().latest_history(&messages)
+}
+
+fn code_block(locale: &str, settings: &SamplerSettings) -> String {
+    format!("```\n{}\n```", settings.describe(locale))
+}
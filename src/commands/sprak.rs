@@ -0,0 +1,33 @@
+use crate::{
+    locale::{Language, TRANSLATIONS},
+    ownership::Manager,
+    prelude::*,
+};
+
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn språk(
+    ctx: Context<'_>,
+    #[description = "Serverns språk, tomt för att följa varje användares språk"] språk: Option<
+        Language,
+    >,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let manager = Manager::from_context(ctx).await;
+    if !manager.guild_admin && !manager.bot_admin {
+        ctx.say(tr!(locale(ctx), "language-not-allowed")).await?;
+        return Ok(());
+    }
+    let language = språk.map(Language::locale);
+    ctx.data().set_guild_locale(guild_id, language)?;
+    let locale = TRANSLATIONS.resolve(language.or_else(|| ctx.locale()));
+    let reply = if språk.is_some() {
+        tr!(locale, "language-changed")
+    } else {
+        tr!(locale, "language-reset")
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
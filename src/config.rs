@@ -1,25 +1,30 @@
 use crate::prelude::*;
 
+use crate::{
+    instruct::InstructTemplate,
+    macros::CustomMacro,
+    preset::PromptPreset,
+    sampler::{SamplerSettings, DEFAULT_MAX_TOKENS},
+};
 use derive_more::Into;
 use parking_lot::RwLock;
 use ron::ser::{to_string_pretty, PrettyConfig};
 use serde::{Deserialize, Serialize};
-use serenity::UserId;
+use serenity::{RoleId, UserId};
 use std::{
     fs::{read_to_string, write},
     sync::LazyLock,
 };
 use tracing::warn;
 
-pub static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(|| {
-    read_to_string("config.ron").map_or_else(
-        |_| Config::create(),
-        |string| match Config::load(string) {
-            Ok(config) => config,
-            Err(why) => panic!("{}", why),
-        },
-    )
-});
+const DEFAULT_CONTEXT_SIZE: usize = 8_192;
+
+#[cfg(not(test))]
+pub static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(Config::open);
+
+/// Tests run on the defaults and never touch `config.ron`.
+#[cfg(test)]
+pub static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(Config::new);
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
@@ -28,18 +33,74 @@ pub struct Config {
     #[serde(default)]
     bot_token: BotToken,
     #[serde(default)]
+    bot_admins: BotAdmins,
+    #[serde(default)]
+    bot_admin_roles: BotAdminRoles,
+    #[serde(default)]
+    backend: BackendConfig,
+    #[serde(default)]
     openai_url: OpenAiUrl,
     #[serde(default)]
     openai_key: OpenAiKey,
     #[serde(default)]
     openai_model: OpenAiModel,
     #[serde(default)]
+    instruct_template: InstructTemplateName,
+    #[serde(default)]
+    instruct_templates: InstructTemplates,
+    #[serde(default)]
+    prompt_preset: PromptPresetName,
+    #[serde(default)]
+    prompt_presets: PromptPresets,
+    #[serde(default)]
+    macros: Macros,
+    #[serde(default)]
+    context_sizes: ContextSizes,
+    #[serde(default)]
+    summary_threshold: SummaryThreshold,
+    #[serde(default)]
+    summary_turns: SummaryTurns,
+    #[serde(default)]
+    lorebook_scan_depth: LorebookScanDepth,
+    #[serde(default)]
+    lorebook_budget: LorebookBudget,
+    #[serde(default)]
+    use_webhooks: UseWebhooks,
+    #[serde(default)]
+    stream_interval_ms: StreamIntervalMs,
+    #[serde(default)]
+    sampler: Sampler,
+    #[serde(default)]
+    sampler_presets: SamplerPresets,
+    #[serde(default)]
     name_substitutes: NameSubstitutes,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
 pub struct BotToken(pub String);
 
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
+pub struct BotAdmins(pub Vec<UserId>);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
+pub struct BotAdminRoles(pub Vec<RoleId>);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub enum BackendConfig {
+    #[default]
+    OpenAi,
+    Ollama {
+        url: String,
+        model: String,
+    },
+    KoboldCpp {
+        url: String,
+    },
+    LlamaCpp {
+        url: String,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Into)]
 pub struct OpenAiUrl(pub String);
 
@@ -49,14 +110,68 @@ pub struct OpenAiKey(pub String);
 #[derive(Debug, Serialize, Deserialize, Clone, Into)]
 pub struct OpenAiModel(pub String);
 
+#[derive(Debug, Serialize, Deserialize, Clone, Into)]
+pub struct InstructTemplateName(pub String);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
+pub struct InstructTemplates(pub Vec<InstructTemplate>);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Into)]
+pub struct PromptPresetName(pub String);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
+pub struct PromptPresets(pub Vec<PromptPreset>);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
+pub struct Macros(pub Vec<CustomMacro>);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Into)]
+pub struct ContextSizes(pub Vec<(String, usize)>);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct SummaryThreshold(pub usize);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct SummaryTurns(pub usize);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct LorebookScanDepth(pub usize);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct LorebookBudget(pub usize);
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct UseWebhooks(pub bool);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Into)]
+pub struct StreamIntervalMs(pub u64);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Into)]
+pub struct Sampler(pub SamplerSettings);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Into)]
+pub struct SamplerPresets(pub Vec<(String, SamplerSettings)>);
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Into)]
 pub struct NameSubstitutes(pub Vec<(String, String)>);
 
+// Only `new` is used in tests.
+#[cfg_attr(test, allow(dead_code))]
 impl Config {
     fn new() -> RwLock<Self> {
         RwLock::new(Self::default())
     }
 
+    fn open() -> RwLock<Self> {
+        read_to_string("config.ron").map_or_else(
+            |_| Self::create(),
+            |string| match Self::load(string) {
+                Ok(config) => config,
+                Err(why) => panic!("{}", why),
+            },
+        )
+    }
+
     fn create() -> RwLock<Self> {
         let config = Self::new();
         let try_save = config.read().save();
@@ -80,7 +195,9 @@ impl Config {
             to_string_pretty(self, PrettyConfig::default())?,
         )?)
     }
+}
 
+impl Config {
     #[inline]
     pub const fn bot_id(&self) -> UserId {
         self.bot_id
@@ -91,6 +208,21 @@ impl Config {
         self.bot_token.clone()
     }
 
+    #[inline]
+    pub fn bot_admins(&self) -> &[UserId] {
+        &self.bot_admins.0
+    }
+
+    #[inline]
+    pub fn bot_admin_roles(&self) -> &[RoleId] {
+        &self.bot_admin_roles.0
+    }
+
+    #[inline]
+    pub fn backend(&self) -> BackendConfig {
+        self.backend.clone()
+    }
+
     #[inline]
     pub fn openai_url(&self) -> OpenAiUrl {
         self.openai_url.clone()
@@ -105,6 +237,97 @@ impl Config {
     pub fn openai_model(&self) -> OpenAiModel {
         self.openai_model.clone()
     }
+
+    pub fn model(&self) -> &str {
+        match &self.backend {
+            BackendConfig::OpenAi => self.openai_model.as_str(),
+            BackendConfig::Ollama { model, .. } => model,
+            BackendConfig::KoboldCpp { .. } | BackendConfig::LlamaCpp { .. } => "",
+        }
+    }
+
+    pub fn instruct_template(&self) -> InstructTemplate {
+        InstructTemplate::find(&self.instruct_template.0, &self.instruct_templates.0)
+            .unwrap_or_else(|| {
+                warn!(
+                    "unknown instruct template {}, using ChatML",
+                    self.instruct_template.0
+                );
+                InstructTemplate::chatml()
+            })
+    }
+
+    pub fn prompt_preset(&self, name: Option<&str>) -> PromptPreset {
+        let name = name.unwrap_or(&self.prompt_preset.0);
+        self.prompt_preset_named(name).unwrap_or_else(|| {
+            warn!("unknown prompt preset {name}, using Svenska");
+            PromptPreset::svenska()
+        })
+    }
+
+    pub fn prompt_preset_named(&self, name: &str) -> Option<PromptPreset> {
+        PromptPreset::find(name, &self.prompt_presets.0)
+    }
+
+    pub fn prompt_preset_names(&self) -> Vec<String> {
+        PromptPreset::names(&self.prompt_presets.0)
+    }
+
+    #[inline]
+    pub fn macros(&self) -> Vec<CustomMacro> {
+        self.macros.0.clone()
+    }
+
+    pub fn context_size(&self) -> usize {
+        self.context_sizes.for_model(self.model())
+    }
+
+    #[inline]
+    pub const fn summary_threshold(&self) -> usize {
+        self.summary_threshold.0
+    }
+
+    #[inline]
+    pub const fn summary_turns(&self) -> usize {
+        self.summary_turns.0
+    }
+
+    #[inline]
+    pub const fn lorebook_scan_depth(&self) -> usize {
+        self.lorebook_scan_depth.0
+    }
+
+    #[inline]
+    pub const fn lorebook_budget(&self) -> usize {
+        self.lorebook_budget.0
+    }
+
+    #[inline]
+    pub const fn use_webhooks(&self) -> bool {
+        self.use_webhooks.0
+    }
+
+    #[inline]
+    pub const fn stream_interval_ms(&self) -> u64 {
+        self.stream_interval_ms.0
+    }
+
+    #[inline]
+    pub fn sampler(&self) -> SamplerSettings {
+        self.sampler.0.clone()
+    }
+
+    pub fn sampler_preset(&self, name: &str) -> Option<SamplerSettings> {
+        self.sampler_presets
+            .0
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, settings)| settings.clone())
+    }
+
+    pub fn sampler_presets(&self) -> Vec<(String, SamplerSettings)> {
+        self.sampler_presets.0.clone()
+    }
 }
 
 impl BotToken {
@@ -113,6 +336,122 @@ impl BotToken {
     }
 }
 
+impl OpenAiModel {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ContextSizes {
+    pub fn for_model(&self, model: &str) -> usize {
+        self.0
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(DEFAULT_CONTEXT_SIZE, |(_, context_size)| *context_size)
+    }
+}
+
+impl Default for InstructTemplateName {
+    fn default() -> Self {
+        Self("ChatML".into())
+    }
+}
+
+impl Default for PromptPresetName {
+    fn default() -> Self {
+        Self("Svenska".into())
+    }
+}
+
+impl Default for ContextSizes {
+    fn default() -> Self {
+        Self(vec![
+            ("gpt-4o".into(), 128_000),
+            ("gpt-4-turbo".into(), 128_000),
+            ("gpt-4".into(), 8_192),
+            ("gpt-3.5-turbo".into(), 16_385),
+        ])
+    }
+}
+
+impl Default for SummaryThreshold {
+    fn default() -> Self {
+        Self(4_000)
+    }
+}
+
+impl Default for SummaryTurns {
+    fn default() -> Self {
+        Self(10)
+    }
+}
+
+impl Default for LorebookScanDepth {
+    fn default() -> Self {
+        Self(4)
+    }
+}
+
+impl Default for LorebookBudget {
+    fn default() -> Self {
+        Self(1_024)
+    }
+}
+
+impl Default for StreamIntervalMs {
+    fn default() -> Self {
+        Self(1_000)
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self(SamplerSettings {
+            temperature: Some(1.3),
+            frequency_penalty: Some(0.5),
+            presence_penalty: Some(0.5),
+            max_tokens: Some(DEFAULT_MAX_TOKENS),
+            ..SamplerSettings::default()
+        })
+    }
+}
+
+impl Default for SamplerPresets {
+    fn default() -> Self {
+        Self(vec![
+            (
+                "Kreativ".into(),
+                SamplerSettings {
+                    temperature: Some(1.5),
+                    min_p: Some(0.05),
+                    ..SamplerSettings::default()
+                },
+            ),
+            (
+                "Balanserad".into(),
+                SamplerSettings {
+                    temperature: Some(1.0),
+                    top_p: Some(0.95),
+                    min_p: Some(0.05),
+                    ..SamplerSettings::default()
+                },
+            ),
+            (
+                "Exakt".into(),
+                SamplerSettings {
+                    temperature: Some(0.7),
+                    top_p: Some(0.9),
+                    top_k: Some(40),
+                    frequency_penalty: Some(0.0),
+                    presence_penalty: Some(0.0),
+                    ..SamplerSettings::default()
+                },
+            ),
+        ])
+    }
+}
+
 impl Default for OpenAiUrl {
     fn default() -> Self {
         Self("https://api.openai.com/v1".into())
@@ -1,107 +1,323 @@
 #![allow(clippy::unreadable_literal)]
 use crate::prelude::*;
 use crate::{
-    commands::{chat::prata, gubbar::gubbar, gubbe::gubbe},
+    backend::Backend,
+    branch::Branch,
+    cadence::RateLimits,
+    character::visible_in,
+    commands::{
+        chat::prata, fortsatt::fortsätt, grenar::grenar, grupp::grupp, gubbar::gubbar,
+        gubbe::gubbe, lorebok::lorebok, makro::makro, mall::mall, persona::persona,
+        sammanfatta::sammanfatta, sampler::sampler, sprak::språk,
+    },
     event_handler::event_handler,
+    locale::localize_commands,
+    lorebook::{Lorebook, LorebookRef},
+    macros::CustomMacro,
+    persona::{Persona, UserPersonas},
+    preset::PromptPreset,
+    sampler::{self, SamplerSettings},
+    storage::{RedbStorage, Storage},
+    tokenizer::Tokenizer,
 };
-use async_openai::{config::OpenAIConfig, Client};
-use dashmap::DashMap;
-use futures::{Stream, StreamExt};
+use dashmap::{DashMap, DashSet};
+use futures::{stream::AbortHandle, Stream, StreamExt};
 use itertools::Itertools;
-use poise::serenity_prelude::{ActivityData, ActivityType, MessageId};
+use poise::serenity_prelude::{
+    ActivityData, ActivityType, ChannelId, GuildId, MessageId, UserId, Webhook,
+};
 use poise::PrefixFrameworkOptions;
 use poise::{
-    serenity_prelude::{ClientBuilder, GatewayIntents, Message},
+    serenity_prelude::{ClientBuilder, GatewayIntents, HttpBuilder, Message},
     Framework, FrameworkOptions,
 };
 use small_fixed_array::FixedString;
-use std::fs::{read, write};
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 const GATEWAY_INTENTS: GatewayIntents =
     GatewayIntents::non_privileged().union(GatewayIntents::MESSAGE_CONTENT);
 
 #[derive(Debug)]
 pub struct Data {
-    pub characters: DashMap<String, Character>,
-    pub chats: DashMap<MessageId, History>,
-    pub ai: Client<OpenAIConfig>,
+    pub storage: Box<dyn Storage>,
+    pub backend: Box<dyn Backend>,
+    pub tokenizer: Tokenizer,
+    pub context_size: usize,
+    pub webhooks: DashMap<ChannelId, Webhook>,
+    pub generations: DashMap<MessageId, (UserId, AbortHandle)>,
+    pub busy_messages: DashSet<MessageId>,
+    pub rate_limits: Arc<RateLimits>,
 }
 
 impl Data {
     pub async fn start_bot() -> Result<()> {
-        let data = Self::load();
+        let data = Self::load().await?;
         start_bot(data).await?;
         Ok(())
     }
 
-    pub fn character(&self, character_name: &str) -> Option<Character> {
-        self.characters.get(character_name).map(|c| c.clone())
+    pub fn character(
+        &self,
+        character_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Character>> {
+        if guild_id.is_some() {
+            if let Some(character) = self.storage.character(character_name, guild_id)? {
+                return Ok(Some(character));
+            }
+        }
+        self.storage.character(character_name, None)
+    }
+
+    pub fn characters(&self, guild_id: Option<GuildId>) -> Result<Vec<Character>> {
+        Ok(visible_in(self.storage.characters()?, guild_id))
     }
 
-    pub fn characters(&self) -> Vec<Character> {
-        self.characters.iter().map(|c| c.clone()).collect_vec()
+    pub fn public_characters(&self) -> Result<Vec<Character>> {
+        self.characters(None)
     }
 
-    pub fn history(&self, message: &Message) -> Option<History> {
-        self.chats.get(&message.id).map(|c| c.clone())
+    /// The chat that `message` belongs to, whether it is the head of a reply
+    /// or one of the parts a long reply was split into.
+    pub fn history(&self, message: &Message) -> Result<Option<History>> {
+        let head = self.storage.reply_head(message.id)?;
+        self.storage.history(head.unwrap_or(message.id))
     }
 
-    pub fn insert_history(&self, history: History) {
-        self.chats.insert(history.id, history);
-        self.save();
+    pub fn latest_history(&self, messages: &[Message]) -> Result<Option<History>> {
+        for message in messages {
+            if let Some(history) = self.history(message)? {
+                return Ok(Some(history));
+            }
+        }
+        Ok(None)
     }
 
-    pub fn insert_character(&self, character: Character) {
-        let character_name = character.name.to_string();
-        self.characters.insert(character_name, character);
-        self.save();
+    pub fn insert_history(&self, history: History) -> Result<()> {
+        self.storage.insert_history(&history)
     }
 
-    pub fn remove_character(&self, character_name: &str) -> Option<()> {
-        self.characters.remove(character_name).map(|_| ())
+    pub fn branches(&self, root: MessageId) -> Result<Vec<Branch>> {
+        self.storage.branches(root)
     }
 
-    pub fn load() -> Self {
-        let characters = read("characters.ron").map_or_else(
-            |_| DashMap::new(),
-            |bytes| ron::de::from_bytes(&bytes).expect("valid characters file"),
-        );
-        let chats = read("chats.ron").map_or_else(
-            |_| DashMap::new(),
-            |bytes| ron::de::from_bytes(&bytes).expect("valid chats file"),
-        );
-        let config = OpenAIConfig::default()
-            .with_api_key(CONFIG.read().openai_key())
-            .with_api_base(CONFIG.read().openai_url());
-        let ai = Client::with_config(config);
-        Self {
-            characters,
-            chats,
-            ai,
-        }
+    pub fn reply_parts(&self, message_id: MessageId) -> Result<Vec<MessageId>> {
+        self.storage.reply_parts(message_id)
+    }
+
+    pub fn set_reply_parts(&self, message_id: MessageId, parts: &[MessageId]) -> Result<()> {
+        self.storage.set_reply_parts(message_id, parts)
+    }
+
+    pub fn insert_character(&self, character: Character) -> Result<()> {
+        self.storage.insert_character(&character)
+    }
+
+    pub fn remove_character(&self, character: &Character) -> Result<Option<Character>> {
+        self.storage
+            .remove_character(&character.name.to_string(), character.guild)
+    }
+
+    pub fn move_character(&self, character: &Character, from: Option<GuildId>) -> Result<bool> {
+        self.storage.move_character(character, from)
+    }
+
+    /// The picture of the PNG card the character was imported from.
+    pub fn avatar(&self, character: &Character) -> Result<Option<Vec<u8>>> {
+        self.storage
+            .avatar(&character.name.to_string(), character.guild)
     }
 
-    pub fn save(&self) {
-        let serialized_characters = ron::to_string(&self.characters);
-        let serialized_chats = ron::to_string(&self.chats);
+    pub fn set_avatar(&self, character: &Character, png: Option<&[u8]>) -> Result<()> {
+        self.storage.set_avatar(character, png)
+    }
 
-        match (serialized_characters, serialized_chats) {
-            (Ok(characters), Ok(chats)) => {
-                write("characters.ron", characters).ok();
-                write("chats.ron", chats).ok();
+    /// The server's own lorebook called `lorebook_name`, or else the shared one.
+    pub fn lorebook(
+        &self,
+        lorebook_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Lorebook>> {
+        if guild_id.is_some() {
+            if let Some(lorebook) = self.storage.lorebook(lorebook_name, guild_id)? {
+                return Ok(Some(lorebook));
             }
-            _ => {
-                tracing::warn!("Failed to serialize characters or chats.");
+        }
+        self.storage.lorebook(lorebook_name, None)
+    }
+
+    pub fn lorebooks(&self, guild_id: Option<GuildId>) -> Result<Vec<Lorebook>> {
+        Ok(visible_in(self.storage.lorebooks()?, guild_id))
+    }
+
+    pub fn insert_lorebook(&self, lorebook: &Lorebook) -> Result<()> {
+        self.storage.insert_lorebook(lorebook)
+    }
+
+    pub fn remove_lorebook(&self, lorebook: &Lorebook) -> Result<Option<Lorebook>> {
+        self.storage.remove_lorebook(&lorebook.name, lorebook.guild)
+    }
+
+    pub fn channel_lorebooks(&self, channel_id: ChannelId) -> Result<Vec<LorebookRef>> {
+        self.storage.channel_lorebooks(channel_id)
+    }
+
+    pub fn set_channel_lorebooks(
+        &self,
+        channel_id: ChannelId,
+        lorebooks: &[LorebookRef],
+    ) -> Result<()> {
+        self.storage.set_channel_lorebooks(channel_id, lorebooks)
+    }
+
+    pub fn personas(&self, user_id: UserId) -> Result<UserPersonas> {
+        self.storage.personas(user_id)
+    }
+
+    pub fn set_personas(&self, user_id: UserId, personas: &UserPersonas) -> Result<()> {
+        self.storage.set_personas(user_id, personas)
+    }
+
+    pub fn active_persona(
+        &self,
+        user_id: UserId,
+        channel_id: ChannelId,
+    ) -> Result<Option<Persona>> {
+        Ok(self.personas(user_id)?.active_in(channel_id).cloned())
+    }
+
+    pub fn sampler_for(&self, history: &History) -> Result<SamplerSettings> {
+        let character = self.current_character(history)?;
+        Ok(sampler::resolve(&character.sampler, &history.sampler))
+    }
+
+    pub fn channel_prompt_preset(&self, channel_id: ChannelId) -> Result<Option<String>> {
+        self.storage.channel_prompt_preset(channel_id)
+    }
+
+    pub fn set_channel_prompt_preset(
+        &self,
+        channel_id: ChannelId,
+        preset_name: Option<&str>,
+    ) -> Result<()> {
+        self.storage
+            .set_channel_prompt_preset(channel_id, preset_name)
+    }
+
+    pub fn prompt_preset_for(
+        &self,
+        history: &History,
+        channel_id: ChannelId,
+    ) -> Result<PromptPreset> {
+        let preset_name = match self.current_character(history)?.prompt_preset {
+            Some(preset_name) => Some(preset_name),
+            None => self.channel_prompt_preset(channel_id)?,
+        };
+        Ok(CONFIG.read().prompt_preset(preset_name.as_deref()))
+    }
+
+    pub fn guild_locale(&self, guild_id: GuildId) -> Result<Option<String>> {
+        self.storage.guild_locale(guild_id)
+    }
+
+    pub fn set_guild_locale(&self, guild_id: GuildId, locale: Option<&str>) -> Result<()> {
+        self.storage.set_guild_locale(guild_id, locale)
+    }
+
+    pub fn channel_macros(&self, channel_id: ChannelId) -> Result<Vec<CustomMacro>> {
+        self.storage.channel_macros(channel_id)
+    }
+
+    pub fn set_channel_macros(&self, channel_id: ChannelId, macros: &[CustomMacro]) -> Result<()> {
+        self.storage.set_channel_macros(channel_id, macros)
+    }
+
+    /// The channel's own macros come first, so they win over the config's.
+    pub fn macros_for(&self, channel_id: ChannelId) -> Result<Vec<CustomMacro>> {
+        let mut macros = self.channel_macros(channel_id)?;
+        macros.extend(CONFIG.read().macros());
+        Ok(macros)
+    }
+
+    fn current_character(&self, history: &History) -> Result<Character> {
+        Ok(self
+            .character(&history.character.name.to_string(), history.character.guild)?
+            .unwrap_or_else(|| history.character.clone()))
+    }
+
+    pub fn lorebooks_for(
+        &self,
+        character: &Character,
+        channel_id: ChannelId,
+    ) -> Result<Vec<Lorebook>> {
+        let mut lorebooks = Vec::<Lorebook>::new();
+        let attached = character
+            .lorebooks
+            .iter()
+            .map(|lorebook_name| self.lorebook(lorebook_name, character.guild));
+        let in_channel = self
+            .channel_lorebooks(channel_id)?
+            .into_iter()
+            .map(|lorebook| self.storage.lorebook(&lorebook.name, lorebook.guild));
+        for lorebook in attached.chain(in_channel) {
+            let Some(lorebook) = lorebook? else {
+                continue;
+            };
+            if !lorebooks
+                .iter()
+                .any(|other| other.name == lorebook.name && other.guild == lorebook.guild)
+            {
+                lorebooks.push(lorebook);
             }
         }
+        Ok(lorebooks)
+    }
+
+    pub async fn load() -> Result<Self> {
+        let storage = Box::new(RedbStorage::open()?);
+        let backend = crate::backend::load();
+        let tokenizer = Tokenizer::for_model(CONFIG.read().model());
+        let context_size = match backend.context_size().await {
+            Ok(Some(context_size)) => context_size,
+            Ok(None) => CONFIG.read().context_size(),
+            Err(why) => {
+                tracing::warn!("could not ask the backend for its context size! {why}");
+                CONFIG.read().context_size()
+            }
+        };
+        Ok(Self {
+            storage,
+            backend,
+            tokenizer,
+            context_size,
+            webhooks: DashMap::new(),
+            generations: DashMap::new(),
+            busy_messages: DashSet::new(),
+            rate_limits: Arc::default(),
+        })
     }
 }
 
 async fn start_bot(data: Data) -> Result<()> {
     let bot_token = CONFIG.read().bot_token();
 
-    let bot_commands = vec![prata(), gubbe(), gubbar(), register()];
+    let mut bot_commands = vec![
+        prata(),
+        gubbe(),
+        gubbar(),
+        grupp(),
+        fortsätt(),
+        grenar(),
+        sammanfatta(),
+        lorebok(),
+        mall(),
+        makro(),
+        persona(),
+        sampler(),
+        språk(),
+        register(),
+    ];
+    localize_commands(&mut bot_commands);
 
     let framework_options = FrameworkOptions {
         commands: bot_commands,
@@ -116,7 +332,15 @@ async fn start_bot(data: Data) -> Result<()> {
 
     let framework = Framework::builder().options(framework_options).build();
 
-    ClientBuilder::new(bot_token.as_str(), GATEWAY_INTENTS)
+    let mut http = HttpBuilder::new(bot_token.as_str()).build();
+    if let Some(ratelimiter) = &mut http.ratelimiter {
+        let rate_limits = Arc::clone(&data.rate_limits);
+        ratelimiter.set_ratelimit_callback(Box::new(move |info| {
+            rate_limits.record(&info.path, info.timeout, Instant::now());
+        }));
+    }
+
+    ClientBuilder::new_with_http(http, GATEWAY_INTENTS)
         .framework(framework)
         .activity(ActivityData {
             name: FixedString::from_str_trunc("Heroes of the Storm"),
@@ -142,7 +366,12 @@ async fn error_handler(error: FrameworkError<'_>) {
     match error {
         poise::FrameworkError::Command { error, ctx, .. } => {
             let command_name = &ctx.command().name;
-            let error_message = format!("error in command: `{command_name}`: {error:?}");
+            let error_message = tr!(
+                locale(ctx),
+                "command-error",
+                command = command_name.to_string(),
+                error = format!("{error:?}")
+            );
             ctx.reply(error_message).await.expect("a");
         }
         error => {
@@ -153,13 +382,30 @@ async fn error_handler(error: FrameworkError<'_>) {
     };
 }
 
+pub async fn autocomplete_lorebook_name<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let lorebook_names = ctx
+        .data()
+        .lorebooks(ctx.guild_id())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|lorebook| lorebook.name)
+        .collect_vec();
+    futures::stream::iter(lorebook_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
+
 pub async fn autocomplete_character_name<'a>(
     ctx: Context<'_>,
     partial: &'a str,
 ) -> impl Stream<Item = String> + 'a {
     let character_names = ctx
         .data()
-        .characters()
+        .characters(ctx.guild_id())
+        .unwrap_or_default()
         .into_iter()
         .map(|a| a.name.to_string())
         .collect_vec();
@@ -167,3 +413,77 @@ pub async fn autocomplete_character_name<'a>(
         futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
     })
 }
+
+pub async fn autocomplete_persona_name<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let persona_names = ctx
+        .data()
+        .personas(ctx.author().id)
+        .unwrap_or_default()
+        .personas
+        .into_iter()
+        .map(|persona| persona.name)
+        .collect_vec();
+    futures::stream::iter(persona_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
+
+pub async fn autocomplete_macro_name<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let macro_names = ctx
+        .data()
+        .channel_macros(ctx.channel_id())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|custom_macro| custom_macro.name)
+        .collect_vec();
+    futures::stream::iter(macro_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
+
+pub async fn autocomplete_sampler_preset<'a>(
+    _: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let preset_names = CONFIG
+        .read()
+        .sampler_presets()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect_vec();
+    futures::stream::iter(preset_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
+
+pub async fn autocomplete_public_character_name<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let character_names = ctx
+        .data()
+        .public_characters()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|character| character.name.to_string())
+        .collect_vec();
+    futures::stream::iter(character_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
+
+pub async fn autocomplete_prompt_preset<'a>(
+    _: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let preset_names = CONFIG.read().prompt_preset_names();
+    futures::stream::iter(preset_names).filter(move |name| {
+        futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
+    })
+}
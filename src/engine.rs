@@ -0,0 +1,278 @@
+use crate::prelude::*;
+
+use crate::{
+    backend::{FinishReason, Generation, GenerationRequest, StreamEvent},
+    discord::Data,
+    group::select_speaker,
+    lorebook::select_entries,
+    macros::Macros,
+    prompt::Prompt,
+    summary::summarise_if_needed,
+};
+use futures::{
+    stream::{AbortHandle, Abortable, BoxStream},
+    StreamExt,
+};
+use serenity::{ChannelId, MessageId, UserId};
+use std::time::Instant;
+
+pub fn start_turn(history: &mut History, message: impl Into<SuperMessage>) {
+    if let Some(choice) = history.choices.get(history.current_page) {
+        history.push_message(choice.clone());
+    }
+    history.push_message(message);
+}
+
+pub async fn stream_reply<'a>(
+    data: &'a Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<BoxStream<'a, Result<StreamEvent>>> {
+    if let Err(why) = summarise_if_needed(data, history, channel_id).await {
+        tracing::warn!("could not summarise chat! {why}");
+    }
+    let request = create_request(data, history, channel_id)?;
+    Ok(data.backend.stream(request))
+}
+
+pub fn stream_continuation<'a>(
+    data: &'a Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<BoxStream<'a, Result<StreamEvent>>> {
+    let prefill = history
+        .choices
+        .get(history.current_page)
+        .map(|choice| choice.message.clone())
+        .unwrap_or_default();
+    let request = create_request(data, history, channel_id)?.prefill(prefill);
+    Ok(data.backend.stream(request))
+}
+
+/// Forgets the generation's stop handle when dropped, however the stream ends.
+pub struct TrackedGeneration<'a> {
+    data: &'a Data,
+    message_id: MessageId,
+}
+
+impl TrackedGeneration<'_> {
+    /// Whether the generation was stopped before it finished.
+    pub fn finish(self) -> bool {
+        self.data
+            .generations
+            .get(&self.message_id)
+            .is_some_and(|generation| generation.1.is_aborted())
+    }
+}
+
+impl Drop for TrackedGeneration<'_> {
+    fn drop(&mut self) {
+        self.data.generations.remove(&self.message_id);
+    }
+}
+
+pub fn track_generation<'a, 'b>(
+    data: &'b Data,
+    message_id: MessageId,
+    requester: UserId,
+    stream: BoxStream<'a, Result<StreamEvent>>,
+) -> (TrackedGeneration<'b>, BoxStream<'a, Result<StreamEvent>>) {
+    let (handle, registration) = AbortHandle::new_pair();
+    data.generations.insert(message_id, (requester, handle));
+    let tracked = TrackedGeneration { data, message_id };
+    (tracked, Abortable::new(stream, registration).boxed())
+}
+
+pub fn generation_requester(data: &Data, message_id: MessageId) -> Option<UserId> {
+    data.generations
+        .get(&message_id)
+        .map(|generation| generation.0)
+}
+
+pub fn stop_generation(data: &Data, message_id: MessageId) -> bool {
+    data.generations
+        .get(&message_id)
+        .map(|generation| generation.1.abort())
+        .is_some()
+}
+
+/// Keeps other buttons off a message until it is dropped.
+pub struct MessageClaim<'a> {
+    data: &'a Data,
+    message_id: MessageId,
+}
+
+impl Drop for MessageClaim<'_> {
+    fn drop(&mut self) {
+        self.data.busy_messages.remove(&self.message_id);
+    }
+}
+
+pub fn claim_message(data: &Data, message_id: MessageId) -> Option<MessageClaim<'_>> {
+    data.busy_messages
+        .insert(message_id)
+        .then_some(MessageClaim { data, message_id })
+}
+
+pub fn record_reply(
+    data: &Data,
+    history: &mut History,
+    text: impl Into<String>,
+    message_id: MessageId,
+    seconds_taken: f64,
+    finish_reason: Option<FinishReason>,
+) -> Result<()> {
+    let reply = SuperMessage::new_assistant(history.character.name.clone(), text);
+    history.fork();
+    history.update(reply, message_id, seconds_taken, finish_reason);
+    data.insert_history(history.clone())
+}
+
+pub fn record_swipe(
+    data: &Data,
+    history: &mut History,
+    text: impl Into<String>,
+    seconds_taken: f64,
+    finish_reason: Option<FinishReason>,
+) -> Result<()> {
+    let reply = SuperMessage::new_assistant(history.character.name.clone(), text);
+    history.update(reply, history.id, seconds_taken, finish_reason);
+    history.current_page = history.choices.len() - 1;
+    data.insert_history(history.clone())
+}
+
+pub fn record_continuation(
+    data: &Data,
+    history: &mut History,
+    text: &str,
+    seconds_taken: f64,
+    finish_reason: Option<FinishReason>,
+) -> Result<()> {
+    history.continue_choice(history.current_page, text, seconds_taken, finish_reason);
+    data.insert_history(history.clone())
+}
+
+pub fn resume_branch(data: &Data, history: &mut History, message_id: MessageId) -> Result<()> {
+    history.root = Some(history.root());
+    history.parent = Some(history.id);
+    history.id = message_id;
+    data.insert_history(history.clone())
+}
+
+pub fn select_swipe(data: &Data, history: &mut History, page: usize) -> Result<()> {
+    history.current_page = page;
+    data.insert_history(history.clone())
+}
+
+pub fn edit_swipe(
+    data: &Data,
+    history: &mut History,
+    page: usize,
+    text: impl Into<String>,
+) -> Result<()> {
+    history.update_choice(text, page);
+    data.insert_history(history.clone())
+}
+
+pub async fn reply(
+    data: &Data,
+    history: &mut History,
+    message: impl Into<SuperMessage>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> Result<Generation> {
+    start_turn(history, message);
+    select_speaker(data, history, channel_id).await?;
+    let now = Instant::now();
+    let generation = generate(data, history, channel_id).await?;
+    let seconds_taken = now.elapsed().as_secs_f64();
+    record_reply(
+        data,
+        history,
+        generation.text.clone(),
+        message_id,
+        seconds_taken,
+        generation.finish_reason,
+    )?;
+    Ok(generation)
+}
+
+pub async fn swipe(
+    data: &Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<Generation> {
+    let now = Instant::now();
+    let generation = generate(data, history, channel_id).await?;
+    let seconds_taken = now.elapsed().as_secs_f64();
+    record_swipe(
+        data,
+        history,
+        generation.text.clone(),
+        seconds_taken,
+        generation.finish_reason,
+    )?;
+    Ok(generation)
+}
+
+pub async fn continue_reply(
+    data: &Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<Generation> {
+    let now = Instant::now();
+    let mut generation = Generation::default();
+    let mut stream = stream_continuation(data, history, channel_id)?;
+    while let Some(event) = stream.next().await {
+        generation.push(event?);
+    }
+    let seconds_taken = now.elapsed().as_secs_f64();
+    record_continuation(
+        data,
+        history,
+        &generation.text,
+        seconds_taken,
+        generation.finish_reason,
+    )?;
+    Ok(generation)
+}
+
+async fn generate(data: &Data, history: &mut History, channel_id: ChannelId) -> Result<Generation> {
+    let mut generation = Generation::default();
+    let mut stream = stream_reply(data, history, channel_id).await?;
+    while let Some(event) = stream.next().await {
+        generation.push(event?);
+    }
+    if let Some(usage) = generation.usage {
+        history.prompt_tokens = usage.prompt_tokens;
+    }
+    Ok(generation)
+}
+
+fn create_request(
+    data: &Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<GenerationRequest> {
+    let sampler = data.sampler_for(history)?;
+    let budget = data
+        .context_size
+        .saturating_sub(sampler.max_tokens().into());
+    let lorebooks = data.lorebooks_for(&history.character, channel_id)?;
+    let lore = select_entries(&lorebooks, history, &data.tokenizer);
+    let preset = data.prompt_preset_for(history, channel_id)?;
+    let custom_macros = data.macros_for(channel_id)?;
+    let macros = Macros::new(history, &custom_macros);
+    let prompt = Prompt::build(history, &lore, &preset, &macros, &data.tokenizer, budget);
+    if prompt.dropped_turns > 0 {
+        tracing::debug!(
+            "dropped {} turns to fit the context window",
+            prompt.dropped_turns
+        );
+    }
+    history.prompt_tokens = prompt.tokens;
+    Ok(
+        GenerationRequest::new(prompt.messages, history.character.name.to_string())
+            .sampler(sampler),
+    )
+}
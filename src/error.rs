@@ -11,7 +11,39 @@ pub enum Error {
     #[error(transparent)]
     Ron(#[from] ron::Error),
     #[error(transparent)]
+    Database(#[from] redb::DatabaseError),
+    #[error(transparent)]
+    Transaction(Box<redb::TransactionError>),
+    #[error(transparent)]
+    Table(#[from] redb::TableError),
+    #[error(transparent)]
+    Storage(#[from] redb::StorageError),
+    #[error(transparent)]
+    Commit(#[from] redb::CommitError),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    #[error("no character card was found in the file")]
+    MissingCard,
+    #[error("no world info entries were found in the file")]
+    MissingLorebook,
+    #[error("the character card is too large for a PNG chunk")]
+    CardTooLarge,
+    #[error("only files on Discord's CDN can be downloaded")]
+    ForeignDownload,
+    #[error("the file is larger than {0} bytes")]
+    DownloadTooLarge(usize),
+    #[error(transparent)]
     TracingFromEnv(#[from] tracing_subscriber::filter::FromEnvError),
     #[error(transparent)]
     TracingParse(#[from] tracing_subscriber::filter::ParseError),
 }
+
+impl From<redb::TransactionError> for Error {
+    fn from(error: redb::TransactionError) -> Self {
+        Self::Transaction(Box::new(error))
+    }
+}
@@ -0,0 +1,266 @@
+use crate::prelude::*;
+
+use crate::{backend::GenerationRequest, discord::Data, preset::PromptPreset};
+use async_openai::types::Role;
+use itertools::Itertools;
+use poise::serenity_prelude::{ChannelId, MessageId};
+use serde::{Deserialize, Serialize};
+
+const SPEAKER_SCAN_DEPTH: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Group {
+    pub members: Vec<GroupMember>,
+    #[serde(default)]
+    pub strategy: SpeakerStrategy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupMember {
+    pub character: Character,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+#[derive(
+    Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter,
+)]
+pub enum SpeakerStrategy {
+    #[default]
+    #[name = "Turordning"]
+    RoundRobin,
+    #[name = "Omnämnanden (@Namn)"]
+    Mention,
+    #[name = "Naturlig ordning"]
+    Natural,
+}
+
+impl Group {
+    pub fn new(characters: Vec<Character>, strategy: SpeakerStrategy) -> Self {
+        let members = characters
+            .into_iter()
+            .map(|character| GroupMember {
+                character,
+                muted: false,
+            })
+            .collect();
+        Self { members, strategy }
+    }
+
+    pub fn into_history(self, message_id: MessageId) -> Option<History> {
+        let first = self.members.first()?;
+        let mut history = first.character.clone().into_history(message_id);
+        history.group = Some(self);
+        Some(history)
+    }
+
+    pub fn member(&self, character_name: &str) -> Option<&GroupMember> {
+        self.members
+            .iter()
+            .find(|member| member.character.name.to_string() == character_name)
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.members.iter().all(|member| member.muted)
+    }
+
+    pub fn names(&self) -> String {
+        self.members
+            .iter()
+            .map(|member| member.character.name.to_string())
+            .join(", ")
+    }
+
+    fn speakers(&self) -> impl Iterator<Item = &Character> {
+        self.members
+            .iter()
+            .filter(|member| !member.muted)
+            .map(|member| &member.character)
+    }
+
+    fn next_after(&self, character_name: &str) -> Option<Character> {
+        let position = self
+            .members
+            .iter()
+            .position(|member| member.character.name.to_string() == character_name);
+        let start = position.map_or(0, |position| position + 1);
+        self.members
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(self.members.len())
+            .find(|member| !member.muted)
+            .map(|member| member.character.clone())
+    }
+
+    fn mentioned(&self, text: &str, prefix: &str) -> Option<Character> {
+        let text = text.to_lowercase();
+        self.speakers()
+            .filter_map(|character| {
+                let name = format!("{prefix}{}", character.name).to_lowercase();
+                position_of(&text, &name).map(|position| (position, character))
+            })
+            .min_by_key(|(position, _)| *position)
+            .map(|(_, character)| character.clone())
+    }
+
+    fn last_or_next(&self, character_name: &str) -> Option<Character> {
+        self.member(character_name)
+            .filter(|member| !member.muted)
+            .map(|member| member.character.clone())
+            .or_else(|| self.next_after(character_name))
+    }
+}
+
+impl History {
+    pub fn add_group_member(&mut self, character: Character) -> bool {
+        let group = self.group.get_or_insert_with(|| {
+            Group::new(vec![self.character.clone()], SpeakerStrategy::default())
+        });
+        if group.member(&character.name.to_string()).is_some() {
+            return false;
+        }
+        group.members.push(GroupMember {
+            character,
+            muted: false,
+        });
+        true
+    }
+
+    pub fn remove_group_member(&mut self, character_name: &str) -> Option<Character> {
+        let group = self.group.as_mut()?;
+        if group.members.len() <= 1 {
+            return None;
+        }
+        let index = group
+            .members
+            .iter()
+            .position(|member| member.character.name.to_string() == character_name)?;
+        let removed = group.members.remove(index).character;
+        if self.character.name.to_string() == character_name {
+            let members = &group.members;
+            let next = members
+                .iter()
+                .cycle()
+                .skip(index)
+                .take(members.len())
+                .find(|member| !member.muted)
+                .or_else(|| members.get(index % members.len()))?;
+            self.character = next.character.clone();
+        }
+        Some(removed)
+    }
+
+    pub fn toggle_group_member(&mut self, character_name: &str) -> Option<bool> {
+        let member = self
+            .group
+            .as_mut()?
+            .members
+            .iter_mut()
+            .find(|member| member.character.name.to_string() == character_name)?;
+        member.muted = !member.muted;
+        Some(member.muted)
+    }
+}
+
+pub async fn select_speaker(
+    data: &Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<()> {
+    let Some(group) = &history.group else {
+        return Ok(());
+    };
+    let last_speaker = history.character.name.to_string();
+    let text = history
+        .history
+        .last()
+        .map(|message| {
+            message
+                .message
+                .strip_prefix(&format!("{}: ", message.author))
+                .unwrap_or(&message.message)
+                .to_string()
+        })
+        .unwrap_or_default();
+    let speaker = match group.strategy {
+        SpeakerStrategy::RoundRobin => group.next_after(&last_speaker),
+        SpeakerStrategy::Mention => group
+            .mentioned(&text, "@")
+            .or_else(|| group.last_or_next(&last_speaker)),
+        SpeakerStrategy::Natural => match group.mentioned(&text, "") {
+            Some(character) => Some(character),
+            None => match pick_speaker(data, history, group, channel_id).await {
+                Ok(character) => character,
+                Err(why) => {
+                    tracing::warn!("could not pick the next speaker! {why}");
+                    None
+                }
+            }
+            .or_else(|| group.next_after(&last_speaker)),
+        },
+    };
+    if let Some(speaker) = speaker {
+        tracing::debug!("{speaker} speaks next");
+        history.character = speaker;
+    }
+    Ok(())
+}
+
+async fn pick_speaker(
+    data: &Data,
+    history: &History,
+    group: &Group,
+    channel_id: ChannelId,
+) -> Result<Option<Character>> {
+    if group.speakers().count() <= 1 {
+        return Ok(group.speakers().next().cloned());
+    }
+    let preset = data.prompt_preset_for(history, channel_id)?;
+    let request = create_speaker_request(&preset, history, group);
+    let text = data.backend.complete(request).await?.text;
+    Ok(group.mentioned(&text, ""))
+}
+
+fn create_speaker_request(
+    preset: &PromptPreset,
+    history: &History,
+    group: &Group,
+) -> GenerationRequest {
+    let turns = history.turns();
+    let transcript = turns[turns.len().saturating_sub(SPEAKER_SCAN_DEPTH)..]
+        .iter()
+        .map(|turn| {
+            if turn.role == Role::Assistant {
+                format!("{}: {}", turn.author, turn.message)
+            } else {
+                turn.message.clone()
+            }
+        })
+        .join("\n");
+    let speakers = group
+        .speakers()
+        .map(|character| character.name.to_string())
+        .join(", ");
+    let messages = vec![
+        SuperMessage::new_system(&preset.speaker_prompt),
+        SuperMessage::new_user(
+            "User",
+            preset
+                .speaker_question
+                .replace("{{group}}", &speakers)
+                .replace("{{chat}}", &transcript),
+        ),
+    ];
+    GenerationRequest::new(messages, "Gruppchatt").max_tokens(16)
+}
+
+fn position_of(text: &str, name: &str) -> Option<usize> {
+    text.match_indices(name)
+        .map(|(index, _)| index)
+        .find(|index| {
+            let before = text[..*index].chars().next_back();
+            let after = text[index + name.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+}
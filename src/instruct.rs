@@ -0,0 +1,153 @@
+use crate::prelude::*;
+
+use async_openai::types::Role;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct InstructTemplate {
+    pub name: String,
+    pub system_prefix: String,
+    pub system_suffix: String,
+    pub user_prefix: String,
+    pub user_suffix: String,
+    pub assistant_prefix: String,
+    pub assistant_suffix: String,
+    pub stop_sequences: Vec<String>,
+    pub include_names: bool,
+}
+
+impl InstructTemplate {
+    pub fn presets() -> Vec<Self> {
+        vec![
+            Self::chatml(),
+            Self::llama3(),
+            Self::alpaca(),
+            Self::mistral(),
+            Self::vicuna(),
+        ]
+    }
+
+    pub fn chatml() -> Self {
+        Self {
+            name: "ChatML".into(),
+            system_prefix: "<|im_start|>system\n".into(),
+            system_suffix: "<|im_end|>\n".into(),
+            user_prefix: "<|im_start|>user\n".into(),
+            user_suffix: "<|im_end|>\n".into(),
+            assistant_prefix: "<|im_start|>assistant\n".into(),
+            assistant_suffix: "<|im_end|>\n".into(),
+            stop_sequences: vec!["<|im_end|>".into()],
+            include_names: true,
+        }
+    }
+
+    pub fn llama3() -> Self {
+        Self {
+            name: "Llama 3".into(),
+            system_prefix: "<|start_header_id|>system<|end_header_id|>\n\n".into(),
+            system_suffix: "<|eot_id|>".into(),
+            user_prefix: "<|start_header_id|>user<|end_header_id|>\n\n".into(),
+            user_suffix: "<|eot_id|>".into(),
+            assistant_prefix: "<|start_header_id|>assistant<|end_header_id|>\n\n".into(),
+            assistant_suffix: "<|eot_id|>".into(),
+            stop_sequences: vec!["<|eot_id|>".into()],
+            include_names: true,
+        }
+    }
+
+    pub fn alpaca() -> Self {
+        Self {
+            name: "Alpaca".into(),
+            system_prefix: String::new(),
+            system_suffix: "\n\n".into(),
+            user_prefix: "### Instruction:\n".into(),
+            user_suffix: "\n\n".into(),
+            assistant_prefix: "### Response:\n".into(),
+            assistant_suffix: "\n\n".into(),
+            stop_sequences: vec!["### Instruction:".into()],
+            include_names: true,
+        }
+    }
+
+    pub fn mistral() -> Self {
+        Self {
+            name: "Mistral".into(),
+            system_prefix: "[INST] ".into(),
+            system_suffix: " [/INST]".into(),
+            user_prefix: "[INST] ".into(),
+            user_suffix: " [/INST]".into(),
+            assistant_prefix: String::new(),
+            assistant_suffix: "</s>".into(),
+            stop_sequences: vec!["[INST]".into(), "</s>".into()],
+            include_names: true,
+        }
+    }
+
+    pub fn vicuna() -> Self {
+        Self {
+            name: "Vicuna".into(),
+            system_prefix: String::new(),
+            system_suffix: "\n\n".into(),
+            user_prefix: "USER: ".into(),
+            user_suffix: "\n".into(),
+            assistant_prefix: "ASSISTANT: ".into(),
+            assistant_suffix: "</s>\n".into(),
+            stop_sequences: vec!["USER:".into(), "</s>".into()],
+            include_names: true,
+        }
+    }
+
+    pub fn find(name: &str, custom_templates: &[Self]) -> Option<Self> {
+        custom_templates
+            .iter()
+            .cloned()
+            .chain(Self::presets())
+            .find(|template| template.name.eq_ignore_ascii_case(name))
+    }
+
+    fn prefix(&self, role: Role) -> &str {
+        match role {
+            Role::System => &self.system_prefix,
+            Role::Assistant => &self.assistant_prefix,
+            _ => &self.user_prefix,
+        }
+    }
+
+    fn suffix(&self, role: Role) -> &str {
+        match role {
+            Role::System => &self.system_suffix,
+            Role::Assistant => &self.assistant_suffix,
+            _ => &self.user_suffix,
+        }
+    }
+
+    fn content(&self, message: &SuperMessage) -> String {
+        let name_prefix = format!("{}: ", message.author);
+        match message.role {
+            Role::Assistant if self.include_names => format!("{name_prefix}{}", message.message),
+            Role::System | Role::Assistant => message.message.clone(),
+            _ if self.include_names => message.message.clone(),
+            _ => message
+                .message
+                .strip_prefix(&name_prefix)
+                .unwrap_or(&message.message)
+                .to_string(),
+        }
+    }
+
+    pub fn format(&self, messages: &[SuperMessage], character_name: &str) -> String {
+        let mut prompt = String::new();
+        for message in messages {
+            prompt.push_str(self.prefix(message.role));
+            prompt.push_str(&self.content(message));
+            prompt.push_str(self.suffix(message.role));
+        }
+        prompt.push_str(&self.assistant_prefix);
+        if self.include_names {
+            prompt.push_str(character_name);
+            prompt.push(':');
+        }
+        prompt
+    }
+}
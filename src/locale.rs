@@ -0,0 +1,196 @@
+use crate::prelude::*;
+
+use crate::discord::Data;
+use fluent::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use serenity::GuildId;
+use std::sync::LazyLock;
+use tracing::warn;
+use unic_langid::LanguageIdentifier;
+
+pub const DEFAULT_LOCALE: &str = "sv-SE";
+
+const CATALOGUES: [(&[&str], &str); 2] = [
+    (&["sv-SE"], include_str!("../locales/sv-SE.ftl")),
+    (&["en-US", "en-GB"], include_str!("../locales/en-US.ftl")),
+];
+
+pub static TRANSLATIONS: LazyLock<Translations> = LazyLock::new(Translations::load);
+
+#[macro_export]
+macro_rules! tr {
+    ($locale:expr, $id:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut args = fluent::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)*
+        $crate::locale::TRANSLATIONS.get($locale, $id, Some(&args))
+    }};
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Language {
+    #[name = "Svenska"]
+    Svenska,
+    #[name = "English"]
+    English,
+}
+
+impl Language {
+    pub const fn locale(self) -> &'static str {
+        match self {
+            Self::Svenska => "sv-SE",
+            Self::English => "en-US",
+        }
+    }
+}
+
+pub struct Translations {
+    catalogues: Vec<Catalogue>,
+}
+
+struct Catalogue {
+    locales: &'static [&'static str],
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl Translations {
+    fn load() -> Self {
+        let catalogues = CATALOGUES
+            .into_iter()
+            .map(|(locales, source)| {
+                let language = locales[0]
+                    .parse::<LanguageIdentifier>()
+                    .expect("valid language identifier");
+                let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(
+                    |(resource, errors)| {
+                        warn!("errors in the {} catalogue: {errors:?}", locales[0]);
+                        resource
+                    },
+                );
+                let mut bundle = FluentBundle::new_concurrent(vec![language]);
+                bundle.set_use_isolating(false);
+                if let Err(errors) = bundle.add_resource(resource) {
+                    warn!("errors in the {} catalogue: {errors:?}", locales[0]);
+                }
+                Catalogue { locales, bundle }
+            })
+            .collect();
+        Self { catalogues }
+    }
+
+    pub fn resolve(&self, locale: Option<&str>) -> &'static str {
+        locale
+            .and_then(|locale| self.catalogue(locale))
+            .map_or(DEFAULT_LOCALE, |catalogue| catalogue.locales[0])
+    }
+
+    pub fn get(&self, locale: &str, id: &str, args: Option<&FluentArgs>) -> String {
+        self.format(locale, id, None, args)
+            .or_else(|| self.format(DEFAULT_LOCALE, id, None, args))
+            .unwrap_or_else(|| {
+                warn!("missing translation for {id}");
+                id.to_string()
+            })
+    }
+
+    fn format(
+        &self,
+        locale: &str,
+        id: &str,
+        attribute: Option<&str>,
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        let bundle = &self.catalogue(locale)?.bundle;
+        let message = bundle.get_message(id)?;
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute)?.value(),
+            None => message.value()?,
+        };
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            warn!("errors while formatting {id}: {errors:?}");
+        }
+        Some(text.into_owned())
+    }
+
+    fn catalogue(&self, locale: &str) -> Option<&Catalogue> {
+        self.catalogues
+            .iter()
+            .find(|catalogue| catalogue.locales.contains(&locale))
+    }
+}
+
+pub fn locale(ctx: Context<'_>) -> &'static str {
+    pick(ctx.data(), ctx.guild_id(), ctx.locale())
+}
+
+pub fn pick(data: &Data, guild_id: Option<GuildId>, user_locale: Option<&str>) -> &'static str {
+    let guild_locale = guild_id.and_then(|guild_id| {
+        data.guild_locale(guild_id).unwrap_or_else(|why| {
+            warn!("could not read the language of {guild_id}: {why}");
+            None
+        })
+    });
+    TRANSLATIONS.resolve(guild_locale.as_deref().or(user_locale))
+}
+
+pub fn localize_commands(commands: &mut [poise::Command<Data, crate::error::Error>]) {
+    for command in commands {
+        let id = message_id(&command.name);
+        localize_command(command, &id);
+    }
+}
+
+fn localize_command(command: &mut poise::Command<Data, crate::error::Error>, id: &str) {
+    for catalogue in &TRANSLATIONS.catalogues {
+        let main_locale = catalogue.locales[0];
+        let text = |attribute: Option<&str>| TRANSLATIONS.format(main_locale, id, attribute, None);
+        if main_locale == DEFAULT_LOCALE {
+            if command.description.is_none() {
+                command.description = text(Some("description"));
+            }
+            continue;
+        }
+        for &locale in catalogue.locales {
+            if let Some(name) = text(None) {
+                command.name_localizations.insert(locale.into(), name);
+            }
+            if let Some(description) = text(Some("description")) {
+                command
+                    .description_localizations
+                    .insert(locale.into(), description);
+            }
+            for parameter in &mut command.parameters {
+                let parameter_id = message_id(&parameter.name);
+                if let Some(name) = text(Some(&parameter_id)) {
+                    parameter.name_localizations.insert(locale.into(), name);
+                }
+                if let Some(description) = text(Some(&format!("{parameter_id}-description"))) {
+                    parameter
+                        .description_localizations
+                        .insert(locale.into(), description);
+                }
+                for choice in &mut parameter.choices {
+                    let choice_id = format!("{parameter_id}-{}", message_id(&choice.name));
+                    if let Some(name) = text(Some(&choice_id)) {
+                        choice.localizations.insert(locale.into(), name);
+                    }
+                }
+            }
+        }
+    }
+    for subcommand in &mut command.subcommands {
+        let subcommand_id = format!("{id}-{}", message_id(&subcommand.name));
+        localize_command(subcommand, &subcommand_id);
+    }
+}
+
+pub fn message_id(name: &str) -> String {
+    name.to_lowercase()
+        .replace(['å', 'ä'], "a")
+        .replace('ö', "o")
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
@@ -0,0 +1,235 @@
+use crate::prelude::*;
+
+use crate::{ownership::Owned, tokenizer::Tokenizer};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::{GuildId, UserId};
+use std::cmp::Reverse;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Lorebook {
+    pub name: String,
+    #[serde(default)]
+    pub entries: Vec<LoreEntry>,
+    #[serde(default)]
+    pub owner: Option<UserId>,
+    #[serde(default)]
+    pub guild: Option<GuildId>,
+}
+
+/// A lorebook attached to a channel, in the scope it was found in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LorebookRef {
+    pub name: String,
+    pub guild: Option<GuildId>,
+}
+
+impl From<&Lorebook> for LorebookRef {
+    fn from(lorebook: &Lorebook) -> Self {
+        Self {
+            name: lorebook.name.clone(),
+            guild: lorebook.guild,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoreEntry {
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub secondary_keys: Vec<String>,
+    pub content: String,
+    #[serde(default)]
+    pub position: InsertionPosition,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+#[derive(
+    Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter,
+)]
+pub enum InsertionPosition {
+    #[name = "Före beskrivningen"]
+    BeforeDescription,
+    #[default]
+    #[name = "Efter beskrivningen"]
+    AfterDescription,
+    #[name = "Före senaste meddelandet"]
+    BeforeLatest,
+}
+
+const fn enabled() -> bool {
+    true
+}
+
+impl Lorebook {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub const fn owned_by(mut self, owner: UserId, guild: Option<GuildId>) -> Self {
+        self.owner = Some(owner);
+        self.guild = guild;
+        self
+    }
+
+    pub fn from_world_info(name: impl Into<String>, bytes: &[u8]) -> Result<Self> {
+        let world_info = serde_json::from_slice::<Value>(bytes)?;
+        let entries = match world_info.get("entries") {
+            Some(Value::Object(entries)) => entries
+                .values()
+                .sorted_by_key(|entry| entry.get("uid").and_then(Value::as_i64))
+                .filter_map(LoreEntry::from_world_info)
+                .collect(),
+            Some(Value::Array(entries)) => entries
+                .iter()
+                .filter_map(LoreEntry::from_world_info)
+                .collect(),
+            _ => return Err(crate::error::Error::MissingLorebook),
+        };
+        Ok(Self {
+            name: name.into(),
+            entries,
+            ..Self::default()
+        })
+    }
+}
+
+impl Owned for Lorebook {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn owner(&self) -> Option<UserId> {
+        self.owner
+    }
+
+    fn guild(&self) -> Option<GuildId> {
+        self.guild
+    }
+}
+
+impl LoreEntry {
+    pub fn new(keys: &str, content: impl Into<String>) -> Self {
+        Self {
+            keys: split_keys(keys),
+            secondary_keys: Vec::new(),
+            content: content.into(),
+            position: InsertionPosition::default(),
+            priority: 0,
+            enabled: true,
+        }
+    }
+
+    fn from_world_info(entry: &Value) -> Option<Self> {
+        let strings = |field: &str| {
+            entry
+                .get(field)
+                .and_then(Value::as_array)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(ToString::to_string)
+                        .collect_vec()
+                })
+                .unwrap_or_default()
+        };
+        let keys = [strings("key"), strings("keys")].concat();
+        let secondary_keys = [strings("keysecondary"), strings("secondary_keys")].concat();
+        let content = entry.get("content")?.as_str()?.to_string();
+        let position = match entry.get("position") {
+            None => InsertionPosition::default(),
+            Some(position)
+                if position.as_i64() == Some(0) || position.as_str() == Some("before_char") =>
+            {
+                InsertionPosition::BeforeDescription
+            }
+            Some(position)
+                if position.as_i64() == Some(1) || position.as_str() == Some("after_char") =>
+            {
+                InsertionPosition::AfterDescription
+            }
+            Some(_) => InsertionPosition::BeforeLatest,
+        };
+        let priority = entry
+            .get("order")
+            .or_else(|| entry.get("insertion_order"))
+            .and_then(Value::as_i64)
+            .unwrap_or_default();
+        let enabled = entry.get("disable").and_then(Value::as_bool).map_or_else(
+            || {
+                entry
+                    .get("enabled")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true)
+            },
+            |disabled| !disabled,
+        );
+        Some(Self {
+            keys,
+            secondary_keys,
+            content,
+            position,
+            priority,
+            enabled,
+        })
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        let contains = |key: &String| !key.trim().is_empty() && text.contains(&key.to_lowercase());
+        self.enabled
+            && self.keys.iter().any(contains)
+            && (self.secondary_keys.is_empty() || self.secondary_keys.iter().any(contains))
+    }
+}
+
+pub fn split_keys(keys: &str) -> Vec<String> {
+    keys.split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+pub fn select_entries(
+    lorebooks: &[Lorebook],
+    history: &History,
+    tokenizer: &Tokenizer,
+) -> Vec<LoreEntry> {
+    let (scan_depth, budget) = {
+        let config = CONFIG.read();
+        (config.lorebook_scan_depth(), config.lorebook_budget())
+    };
+    let scanned_text = history
+        .turns()
+        .iter()
+        .rev()
+        .take(scan_depth)
+        .map(|message| message.message.as_str())
+        .join("\n");
+    let mut tokens = 0;
+    let mut selected = Vec::new();
+    for entry in lorebooks
+        .iter()
+        .flat_map(|lorebook| &lorebook.entries)
+        .filter(|entry| entry.matches(&scanned_text))
+        .sorted_by_key(|entry| Reverse(entry.priority))
+    {
+        let entry_tokens = tokenizer.count(&entry.content);
+        if tokens + entry_tokens > budget {
+            continue;
+        }
+        tokens += entry_tokens;
+        selected.push(entry.clone());
+    }
+    selected
+}
@@ -0,0 +1,215 @@
+use crate::prelude::*;
+
+use async_openai::types::Role;
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+pub const BUILTIN_MACROS: [&str; 9] = [
+    "char",
+    "user",
+    "date",
+    "time",
+    "random",
+    "pick",
+    "roll",
+    "lastMessage",
+    "idle_duration",
+];
+const MAX_DEPTH: usize = 8;
+/// Macros expanded for one text, nested ones included, after which the rest
+/// is left as it is. Custom macros that use each other many times over would
+/// otherwise grow without bound.
+const MAX_SUBSTITUTIONS: usize = 1_000;
+const MAX_DICE: u32 = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CustomMacro {
+    pub name: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Macros<'a> {
+    character: String,
+    user: String,
+    last_message: String,
+    idle_since: Option<DateTime<Utc>>,
+    chat: u64,
+    now: DateTime<Local>,
+    custom: &'a [CustomMacro],
+}
+
+impl<'a> Macros<'a> {
+    pub fn new(history: &History, custom: &'a [CustomMacro]) -> Self {
+        let turns = history.turns();
+        let last_message = turns
+            .last()
+            .map(|message| {
+                message
+                    .message
+                    .strip_prefix(&format!("{}: ", message.author))
+                    .unwrap_or(&message.message)
+                    .to_string()
+            })
+            .unwrap_or_default();
+        // Like SillyTavern, the message being answered doesn't count: the idle
+        // duration is how long the user had been away before sending it.
+        let idle_since = turns
+            .iter()
+            .rev()
+            .skip(1)
+            .find(|message| message.role == Role::User)
+            .and_then(|message| message.sent_at);
+        Self {
+            character: history.character.name.to_string(),
+            user: history.user_name(),
+            last_message,
+            idle_since,
+            chat: history.root().get(),
+            now: Local::now(),
+            custom,
+        }
+    }
+
+    #[must_use]
+    pub fn at(self, now: DateTime<Local>) -> Self {
+        Self { now, ..self }
+    }
+
+    pub fn expand(&self, text: &str) -> String {
+        let mut budget = MAX_SUBSTITUTIONS;
+        self.expand_at(text, 0, &mut budget)
+    }
+
+    pub fn expand_message(&self, message: &SuperMessage) -> SuperMessage {
+        let mut message = message.clone();
+        message.message = self.expand(&message.message);
+        message
+    }
+
+    fn expand_at(&self, text: &str, depth: usize, budget: &mut usize) -> String {
+        let mut expanded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(length) = rest[start..].find("}}") else {
+                break;
+            };
+            let Some(left) = budget.checked_sub(1) else {
+                break;
+            };
+            *budget = left;
+            let offset = text.len() - rest.len() + start;
+            let body = &rest[start + 2..start + length];
+            expanded.push_str(&rest[..start]);
+            match self.evaluate(body, text, offset, depth, budget) {
+                Some(value) => expanded.push_str(&value),
+                None => expanded.push_str(&rest[start..start + length + 2]),
+            }
+            rest = &rest[start + length + 2..];
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    fn evaluate(
+        &self,
+        body: &str,
+        text: &str,
+        offset: usize,
+        depth: usize,
+        budget: &mut usize,
+    ) -> Option<String> {
+        let (name, arguments) = body
+            .split_once("::")
+            .map(|(name, arguments)| (name, Some(arguments.split("::").collect::<Vec<_>>())))
+            .or_else(|| {
+                body.split_once(':')
+                    .map(|(name, arguments)| (name, Some(arguments.split(',').collect())))
+            })
+            .unwrap_or((body, None));
+        let arguments = arguments.map(|arguments| {
+            arguments
+                .into_iter()
+                .map(str::trim)
+                .filter(|argument| !argument.is_empty())
+                .collect::<Vec<_>>()
+        });
+        match (name.trim().to_lowercase().as_str(), arguments) {
+            ("char", None) => Some(self.character.clone()),
+            ("user", None) => Some(self.user.clone()),
+            ("date", None) => Some(self.now.format("%Y-%m-%d").to_string()),
+            ("time", None) => Some(self.now.format("%H:%M").to_string()),
+            ("lastmessage", None) => Some(self.last_message.clone()),
+            ("idle_duration", None) => Some(humanize(self.idle_duration())),
+            ("random", Some(options)) => fastrand::choice(options).map(str::to_string),
+            ("pick", Some(options)) => Rng::with_seed(self.seed(text, offset))
+                .choice(options)
+                .map(str::to_string),
+            ("roll", Some(dice)) => {
+                roll(&dice.join(","), &mut Rng::new()).map(|sum| sum.to_string())
+            }
+            (_, None) if depth < MAX_DEPTH => self
+                .custom
+                .iter()
+                .find(|custom| custom.name.eq_ignore_ascii_case(name.trim()))
+                .map(|custom| self.expand_at(&custom.text, depth + 1, budget)),
+            _ => None,
+        }
+    }
+
+    fn idle_duration(&self) -> TimeDelta {
+        self.idle_since.map_or_else(TimeDelta::zero, |since| {
+            self.now.with_timezone(&Utc) - since
+        })
+    }
+
+    /// `{{pick}}` keeps its choice for the whole chat, so the seed only depends
+    /// on the chat and where the macro appears.
+    fn seed(&self, text: &str, offset: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.chat, text, offset).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_alphanumeric() || matches!(character, '_' | '-'))
+        && !BUILTIN_MACROS
+            .iter()
+            .any(|builtin| builtin.eq_ignore_ascii_case(name))
+}
+
+pub fn roll(dice: &str, rng: &mut Rng) -> Option<i64> {
+    let dice = dice.to_lowercase().replace(char::is_whitespace, "");
+    let (dice, modifier) = match dice.find(['+', '-']) {
+        Some(index) => (&dice[..index], dice[index..].parse::<i64>().ok()?),
+        None => (dice.as_str(), 0),
+    };
+    let (count, sides) = match dice.split_once('d') {
+        Some(("", sides)) => (1, sides.parse::<u32>().ok()?),
+        Some((count, sides)) => (count.parse::<u32>().ok()?, sides.parse::<u32>().ok()?),
+        None => (1, dice.parse::<u32>().ok()?),
+    };
+    if count == 0 || count > MAX_DICE || sides == 0 {
+        return None;
+    }
+    let sum = (0..count)
+        .map(|_| i64::from(rng.u32(1..=sides)))
+        .sum::<i64>();
+    sum.checked_add(modifier)
+}
+
+fn humanize(duration: TimeDelta) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        0..60 => format!("{seconds} s"),
+        60..3_600 => format!("{} min", seconds / 60),
+        3_600..86_400 => format!("{} h", seconds / 3_600),
+        _ => format!("{} d", seconds / 86_400),
+    }
+}
@@ -1,11 +1,36 @@
+mod backend;
+mod branch;
+mod buttons;
+mod cadence;
+mod card;
 mod character;
 mod commands;
 mod config;
 mod discord;
+mod engine;
 mod error;
 mod event_handler;
+mod group;
+mod instruct;
+mod locale;
+mod lorebook;
+mod macros;
+mod ownership;
+mod persona;
 mod prelude;
+mod preset;
+mod prompt;
+mod render;
+mod reply;
+mod sampler;
+mod split;
+mod storage;
+mod summary;
 mod super_message;
+mod tokenizer;
+
+#[cfg(test)]
+mod tests;
 
 #[tokio::main]
 async fn main() -> prelude::Result<()> {
@@ -0,0 +1,98 @@
+use crate::prelude::*;
+
+use serenity::{ComponentInteraction, GuildId, Permissions, RoleId, UserId};
+
+/// Characters and lorebooks belong to whoever made them, in the server they
+/// were made in, or are shared with every server.
+pub trait Owned {
+    fn name(&self) -> String;
+    fn owner(&self) -> Option<UserId>;
+    fn guild(&self) -> Option<GuildId>;
+}
+
+impl Owned for Character {
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn owner(&self) -> Option<UserId> {
+        self.owner
+    }
+
+    fn guild(&self) -> Option<GuildId> {
+        self.guild
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Manager {
+    pub user_id: UserId,
+    pub guild_id: Option<GuildId>,
+    pub guild_admin: bool,
+    pub bot_admin: bool,
+}
+
+impl Manager {
+    pub async fn from_context(ctx: Context<'_>) -> Self {
+        let user_id = ctx.author().id;
+        let guild_id = ctx.guild_id();
+        let member = ctx.author_member().await;
+        let permissions = member.as_ref().and_then(|member| {
+            member
+                .permissions
+                .or_else(|| ctx.guild().map(|guild| guild.member_permissions(member)))
+        });
+        let roles: Vec<RoleId> = member
+            .as_ref()
+            .map(|member| member.roles.to_vec())
+            .unwrap_or_default();
+        Self::new(user_id, guild_id, permissions, &roles)
+    }
+
+    pub fn from_interaction(interaction: &ComponentInteraction) -> Self {
+        let member = interaction.member.as_deref();
+        let roles: Vec<RoleId> = member
+            .map(|member| member.roles.to_vec())
+            .unwrap_or_default();
+        Self::new(
+            interaction.user.id,
+            interaction.guild_id,
+            member.and_then(|member| member.permissions),
+            &roles,
+        )
+    }
+
+    fn new(
+        user_id: UserId,
+        guild_id: Option<GuildId>,
+        permissions: Option<Permissions>,
+        roles: &[RoleId],
+    ) -> Self {
+        let config = CONFIG.read();
+        Self {
+            user_id,
+            guild_id,
+            guild_admin: permissions.is_some_and(|permissions| {
+                permissions.contains(Permissions::ADMINISTRATOR)
+                    || permissions.contains(Permissions::MANAGE_GUILD)
+            }),
+            bot_admin: config.bot_admins().contains(&user_id)
+                || roles
+                    .iter()
+                    .any(|role| config.bot_admin_roles().contains(role)),
+        }
+    }
+
+    pub fn may_manage(&self, owned: &impl Owned) -> bool {
+        if self.bot_admin || owned.owner() == Some(self.user_id) {
+            return true;
+        }
+        // Unowned legacy characters fall under the guild recorded on them;
+        // shared ones without a guild are left to bot admins.
+        self.guild_admin && self.guild_id.is_some() && owned.guild() == self.guild_id
+    }
+
+    pub fn refusal(locale: &str, character: &Character) -> String {
+        tr!(locale, "not-allowed", character = character.to_string())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use serenity::{ChannelId, UserId};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Persona {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// Who is playing the persona, for the copies kept in a chat.
+    #[serde(default)]
+    pub user_id: Option<UserId>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct UserPersonas {
+    pub personas: Vec<Persona>,
+    #[serde(default)]
+    pub active: HashMap<ChannelId, String>,
+}
+
+impl Persona {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            avatar: None,
+            user_id: None,
+        }
+    }
+}
+
+impl UserPersonas {
+    pub fn persona(&self, name: &str) -> Option<&Persona> {
+        self.personas
+            .iter()
+            .find(|persona| persona.name.to_lowercase() == name.to_lowercase())
+    }
+
+    pub fn active_in(&self, channel_id: ChannelId) -> Option<&Persona> {
+        self.active
+            .get(&channel_id)
+            .and_then(|name| self.persona(name))
+    }
+
+    pub fn insert(&mut self, persona: Persona) -> bool {
+        if let Some(existing) = self
+            .personas
+            .iter_mut()
+            .find(|existing| existing.name.to_lowercase() == persona.name.to_lowercase())
+        {
+            *existing = persona;
+            return true;
+        }
+        self.personas.push(persona);
+        false
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Persona> {
+        let index = self
+            .personas
+            .iter()
+            .position(|persona| persona.name.to_lowercase() == name.to_lowercase())?;
+        let persona = self.personas.remove(index);
+        self.active.retain(|_, active| *active != persona.name);
+        Some(persona)
+    }
+
+    pub fn activate(&mut self, channel_id: ChannelId, name: &str) -> Option<&Persona> {
+        let name = self.persona(name)?.name.clone();
+        self.active.insert(channel_id, name);
+        self.active_in(channel_id)
+    }
+
+    pub fn deactivate(&mut self, channel_id: ChannelId) -> Option<String> {
+        self.active.remove(&channel_id)
+    }
+}
+
+impl History {
+    pub fn set_persona(&mut self, user_id: UserId, mut persona: Persona) {
+        persona.user_id = Some(user_id);
+        match self
+            .personas
+            .iter_mut()
+            .find(|existing| existing.user_id == Some(user_id))
+        {
+            Some(existing) => *existing = persona,
+            None => self.personas.push(persona),
+        }
+    }
+}
@@ -2,9 +2,17 @@ pub use crate::character::Character;
 pub use crate::config::substitute_name;
 pub use crate::config::CONFIG;
 pub use crate::discord::autocomplete_character_name;
+pub use crate::discord::autocomplete_lorebook_name;
+pub use crate::discord::autocomplete_macro_name;
+pub use crate::discord::autocomplete_persona_name;
+pub use crate::discord::autocomplete_prompt_preset;
+pub use crate::discord::autocomplete_public_character_name;
+pub use crate::discord::autocomplete_sampler_preset;
+pub use crate::locale::locale;
 pub use crate::super_message::History;
 pub use crate::super_message::SuperMessage;
 pub use crate::super_message::AVATAR;
+pub use crate::tr;
 pub use poise::serenity_prelude as serenity;
 pub type Result<T, E = crate::error::Error> = std::result::Result<T, E>;
 pub type Context<'a> = poise::Context<'a, crate::discord::Data, crate::error::Error>;
@@ -15,9 +23,10 @@ pub type FrameworkError<'a> = poise::FrameworkError<'a, crate::discord::Data, cr
 use itertools::Itertools;
 use strsim::levenshtein;
 
-pub fn most_similar_name_to(input: impl AsRef<str>, ctx: Context<'_>) -> Option<String> {
-    ctx.data()
-        .characters()
+pub fn most_similar_name_to(input: impl AsRef<str>, ctx: Context<'_>) -> Result<Option<String>> {
+    Ok(ctx
+        .data()
+        .characters(ctx.guild_id())?
         .into_iter()
         .map(|character| character.name.to_string())
         .map(|character_name| (levenshtein(input.as_ref(), &character_name), character_name))
@@ -25,5 +34,5 @@ pub fn most_similar_name_to(input: impl AsRef<str>, ctx: Context<'_>) -> Option<
         .map(|(_, character_name)| character_name)
         .collect_vec()
         .first()
-        .cloned()
+        .cloned())
 }
@@ -0,0 +1,264 @@
+use crate::prelude::*;
+
+use crate::{
+    lorebook::{InsertionPosition, LoreEntry},
+    macros::Macros,
+    preset::{ExampleStrategy, PromptPreset, PromptSection},
+    tokenizer::Tokenizer,
+};
+
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub messages: Vec<SuperMessage>,
+    pub tokens: usize,
+    pub dropped_turns: usize,
+}
+
+impl Prompt {
+    pub fn build(
+        history: &History,
+        lore: &[LoreEntry],
+        preset: &PromptPreset,
+        macros: &Macros,
+        tokenizer: &Tokenizer,
+        budget: usize,
+    ) -> Self {
+        let mut sections = preset.sections.clone();
+        if !sections.contains(&PromptSection::ChatHistory) {
+            sections.push(PromptSection::ChatHistory);
+        }
+        let mut before_history = Vec::new();
+        let mut after_history = Vec::new();
+        let mut authors_note = None;
+        let mut examples_at = None;
+        let mut examples_before_history = None;
+        for section in &sections {
+            match section {
+                PromptSection::ChatHistory => {
+                    before_history = std::mem::take(&mut after_history);
+                    examples_before_history = examples_at.take();
+                }
+                PromptSection::Examples => examples_at = Some(after_history.len()),
+                PromptSection::AuthorsNote { depth, text } => {
+                    authors_note = system(&macros.expand(text)).map(|note| (*depth, note));
+                }
+                section => after_history.extend(render(section, history, lore, macros)),
+            }
+        }
+
+        let latest_lore =
+            lore_at(lore, InsertionPosition::BeforeLatest, macros).collect::<Vec<_>>();
+        let turns = history
+            .unsummarised_turns()
+            .iter()
+            .map(|turn| macros.expand_message(turn))
+            .collect::<Vec<_>>();
+        let blocks = if examples_before_history.is_some() || examples_at.is_some() {
+            examples(history, preset, macros)
+        } else {
+            Vec::new()
+        };
+        let always_examples = match preset.example_strategy {
+            ExampleStrategy::Always => blocks.concat(),
+            ExampleStrategy::UntilChatLength(length) if history.turns().len() < length => {
+                blocks.concat()
+            }
+            _ => Vec::new(),
+        };
+        let mut tokens = before_history
+            .iter()
+            .chain(&after_history)
+            .chain(&always_examples)
+            .chain(&latest_lore)
+            .chain(authors_note.iter().map(|(_, note)| note))
+            .map(|message| tokenizer.count_message(message))
+            .sum::<usize>();
+        let mut kept_turns = Vec::new();
+        for turn in turns.iter().rev().cloned() {
+            let turn_tokens = tokenizer.count_message(&turn);
+            if tokens + turn_tokens > budget && !kept_turns.is_empty() {
+                break;
+            }
+            tokens += turn_tokens;
+            kept_turns.push(turn);
+        }
+        let dropped_turns = turns.len() - kept_turns.len();
+        let examples = if preset.example_strategy == ExampleStrategy::UntilContextFull {
+            fit_examples(blocks, tokenizer, &mut tokens, budget)
+        } else {
+            always_examples
+        };
+        if let Some(index) = examples_before_history {
+            insert_at(&mut before_history, index, examples);
+        } else if let Some(index) = examples_at {
+            insert_at(&mut after_history, index, examples);
+        }
+        kept_turns.reverse();
+        let latest_turn = kept_turns.pop();
+        let mut chat = kept_turns
+            .into_iter()
+            .chain(latest_lore)
+            .chain(latest_turn)
+            .collect::<Vec<_>>();
+        if let Some((depth, note)) = authors_note {
+            chat.insert(chat.len().saturating_sub(depth), note);
+        }
+        let messages = before_history
+            .into_iter()
+            .chain(chat)
+            .chain(after_history)
+            .collect();
+        Self {
+            messages,
+            tokens,
+            dropped_turns,
+        }
+    }
+}
+
+fn render(
+    section: &PromptSection,
+    history: &History,
+    lore: &[LoreEntry],
+    macros: &Macros,
+) -> Vec<SuperMessage> {
+    match section {
+        PromptSection::MainPrompt(text)
+        | PromptSection::Jailbreak(text)
+        | PromptSection::System(text)
+        | PromptSection::PostHistory(text) => system(&macros.expand(text)).into_iter().collect(),
+        PromptSection::User(text) => vec![SuperMessage::new_user(
+            history.user_name(),
+            macros.expand(text),
+        )],
+        PromptSection::Char(text) => vec![SuperMessage::new_assistant(
+            history.character.name.to_string(),
+            macros.expand(text),
+        )],
+        PromptSection::CharacterDescription => {
+            lore_at(lore, InsertionPosition::BeforeDescription, macros)
+                .chain(descriptions(history).map(|description| {
+                    SuperMessage::new_system(macros.expand(&description.message))
+                }))
+                .chain(lore_at(lore, InsertionPosition::AfterDescription, macros))
+                .collect()
+        }
+        PromptSection::Persona(format) => history
+            .personas
+            .iter()
+            .map(|persona| {
+                SuperMessage::new_system(
+                    macros.expand(
+                        &format
+                            .replace("{{user}}", &persona.name)
+                            .replace("{{persona}}", &persona.description),
+                    ),
+                )
+            })
+            .collect(),
+        PromptSection::Summary(format) => history
+            .summary
+            .as_ref()
+            .and_then(|summary| {
+                system(&macros.expand(&format.replace("{{summary}}", &summary.text)))
+            })
+            .into_iter()
+            .collect(),
+        PromptSection::GroupNote(format) => history
+            .group
+            .as_ref()
+            .and_then(|group| system(&macros.expand(&format.replace("{{group}}", &group.names()))))
+            .into_iter()
+            .collect(),
+        PromptSection::ChatHistory
+        | PromptSection::AuthorsNote { .. }
+        | PromptSection::Examples => Vec::new(),
+    }
+}
+
+/// Each example block as the messages it is sent as, headed by the preset's
+/// separator.
+fn examples(history: &History, preset: &PromptPreset, macros: &Macros) -> Vec<Vec<SuperMessage>> {
+    let user_name = history.user_name();
+    let character_name = history.character.name.to_string();
+    history
+        .character
+        .examples()
+        .iter()
+        .map(|block| {
+            let turns = block.turns.iter().flat_map(|turn| {
+                let user = turn.user.iter().map(|text| {
+                    SuperMessage::new_user(
+                        user_name.clone(),
+                        macros.expand(&format!("{user_name}: {text}")),
+                    )
+                });
+                let character = turn.character.iter().map(|text| {
+                    SuperMessage::new_assistant(character_name.clone(), macros.expand(text))
+                });
+                user.chain(character)
+            });
+            system(&macros.expand(&preset.example_separator))
+                .into_iter()
+                .chain(turns)
+                .collect()
+        })
+        .collect()
+}
+
+/// Adds whole blocks in order for as long as they fit in what the chat left over.
+fn fit_examples(
+    blocks: Vec<Vec<SuperMessage>>,
+    tokenizer: &Tokenizer,
+    tokens: &mut usize,
+    budget: usize,
+) -> Vec<SuperMessage> {
+    let mut examples = Vec::new();
+    for block in blocks {
+        let block_tokens = block
+            .iter()
+            .map(|message| tokenizer.count_message(message))
+            .sum::<usize>();
+        if *tokens + block_tokens > budget {
+            break;
+        }
+        *tokens += block_tokens;
+        examples.extend(block);
+    }
+    examples
+}
+
+fn insert_at(messages: &mut Vec<SuperMessage>, index: usize, inserted: Vec<SuperMessage>) {
+    let rest = messages.split_off(index);
+    messages.extend(inserted);
+    messages.extend(rest);
+}
+
+fn system(text: &str) -> Option<SuperMessage> {
+    Some(text)
+        .filter(|text| !text.trim().is_empty())
+        .map(SuperMessage::new_system)
+}
+
+fn lore_at<'a>(
+    lore: &'a [LoreEntry],
+    position: InsertionPosition,
+    macros: &'a Macros,
+) -> impl Iterator<Item = SuperMessage> + 'a {
+    lore.iter()
+        .filter(move |entry| entry.position == position)
+        .map(|entry| SuperMessage::new_system(macros.expand(&entry.content)))
+}
+
+fn descriptions(history: &History) -> impl Iterator<Item = &SuperMessage> {
+    let members = history
+        .group
+        .iter()
+        .flat_map(|group| &group.members)
+        .map(|member| &member.character.description);
+    let own = history
+        .group
+        .is_none()
+        .then_some(&history.character.description);
+    own.into_iter().chain(members)
+}
@@ -0,0 +1,126 @@
+use crate::prelude::*;
+
+use crate::{
+    backend::{FinishReason, StreamEvent},
+    buttons::reply_footer,
+    cadence::Cadence,
+    discord::Data,
+    engine,
+    error::Error,
+    reply::{ReplyMessage, ReplyView},
+};
+use futures::{stream::BoxStream, StreamExt};
+use poise::serenity_prelude::{http::HttpError, CreateActionRow, Http, UserId};
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub text: String,
+    pub seconds_taken: f64,
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn render_stream(
+    http: &Http,
+    data: &Data,
+    history: &mut History,
+    message: &mut ReplyMessage,
+    stream: BoxStream<'_, Result<StreamEvent>>,
+    previous: &str,
+    label: &str,
+    buttons: Vec<CreateActionRow<'static>>,
+    locale: &str,
+    requester: UserId,
+) -> Result<Rendered> {
+    let started = Instant::now();
+    let mut cadence = Cadence::from_config();
+    let (generation, mut stream) = engine::track_generation(data, message.id(), requester, stream);
+    let mut output = String::new();
+    let mut finish_reason = None;
+    let mut pending = false;
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(StreamEvent::Delta(content)) => {
+                output.push_str(&content);
+                pending = true;
+            }
+            Ok(StreamEvent::Finished(reason)) => {
+                tracing::debug!("generation finished: {reason:?}");
+                finish_reason = Some(reason);
+            }
+            Ok(StreamEvent::Usage(usage)) => {
+                history.prompt_tokens = usage.prompt_tokens;
+            }
+            Err(err) => return Err(err),
+        }
+        if !pending || !cadence.is_due(Instant::now()) {
+            continue;
+        }
+        let text = format!("{previous}{output}");
+        let seconds = seconds_since(started);
+        let footer = reply_footer(locale, data, history, label, &text, seconds, None);
+        match message
+            .edit(http, data, &history.character, ReplyView::new(text, footer))
+            .await
+        {
+            Ok(()) => {}
+            // Serenity already told us about the bucket through the callback.
+            Err(err) if is_rate_limited(&err) => {}
+            Err(err) => return Err(err),
+        }
+        match data.rate_limits.take(message.id(), Instant::now()) {
+            Some(reset_after) => {
+                cadence.rate_limited(reset_after, Instant::now());
+                tracing::debug!(
+                    "rate limited while streaming, editing every {:?}",
+                    cadence.interval()
+                );
+            }
+            None => cadence.flushed(Instant::now()),
+        }
+        pending = false;
+    }
+    if generation.finish() {
+        finish_reason = Some(FinishReason::Cancelled);
+    }
+
+    let seconds_taken = seconds_since(started);
+    let text = format!("{previous}{output}");
+    let footer = reply_footer(
+        locale,
+        data,
+        history,
+        label,
+        &text,
+        seconds_taken,
+        finish_reason,
+    );
+    message
+        .edit(
+            http,
+            data,
+            &history.character,
+            ReplyView::new(text, footer).components(buttons),
+        )
+        .await?;
+    Ok(Rendered {
+        text: output,
+        seconds_taken,
+        finish_reason,
+    })
+}
+
+fn seconds_since(started: Instant) -> f64 {
+    format!("{:.1}", started.elapsed().as_secs_f64())
+        .parse::<f64>()
+        .expect("valid time taken")
+}
+
+fn is_rate_limited(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Discord(serenity::Error::Http(HttpError::UnsuccessfulRequest(response)))
+            if response.status_code.as_u16() == 429
+    )
+}
@@ -0,0 +1,336 @@
+use crate::prelude::*;
+
+use crate::{discord::Data, split::split_reply};
+use poise::serenity_prelude::{
+    ChannelId, CreateActionRow, CreateEmbed, CreateEmbedFooter, CreateMessage, CreateWebhook,
+    EditMessage, EditWebhookMessage, ExecuteWebhook, Http, Message, MessageId, Webhook,
+};
+
+const WEBHOOK_NAME: &str = "DiscordTavern";
+const CONTENT_LENGTH: usize = 2000;
+pub const EMBED_LENGTH: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct ReplyMessage {
+    message: Box<Message>,
+    webhook: Option<Box<Webhook>>,
+    parts: Vec<(MessageId, String)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReplyView {
+    pub text: String,
+    pub footer: String,
+    pub note: Option<(String, String)>,
+    pub components: Option<Vec<CreateActionRow<'static>>>,
+}
+
+impl ReplyView {
+    pub fn new(text: impl Into<String>, footer: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            footer: footer.into(),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn note(mut self, title: impl Into<String>, text: impl Into<String>) -> Self {
+        self.note = Some((title.into(), text.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn components(mut self, components: Vec<CreateActionRow<'static>>) -> Self {
+        self.components = Some(components);
+        self
+    }
+
+    fn pages(&self, webhook: bool) -> Vec<String> {
+        let limit = if webhook {
+            CONTENT_LENGTH.saturating_sub(self.subtext().chars().count())
+        } else {
+            EMBED_LENGTH
+        };
+        split_reply(&self.text, limit)
+    }
+
+    fn embed(&self, character: &Character, text: &str) -> CreateEmbed<'static> {
+        let mut embed = CreateEmbed::new()
+            .title(character.to_string())
+            .description(text.to_string())
+            .thumbnail(character.avatar.to_string())
+            .footer(CreateEmbedFooter::new(self.footer.clone()));
+        if let Some((title, text)) = &self.note {
+            embed = embed.field(title.clone(), text.clone(), false);
+        }
+        embed
+    }
+
+    fn subtext(&self) -> String {
+        let mut subtext = self.footer.clone();
+        if let Some((_, text)) = &self.note {
+            subtext = format!("{text} | {subtext}");
+        }
+        format!("\n-# {subtext}")
+    }
+
+    fn content(&self, text: &str) -> String {
+        format!("{text}{}", self.subtext())
+    }
+}
+
+impl ReplyMessage {
+    pub async fn send(
+        http: &Http,
+        data: &Data,
+        reference: &Message,
+        character: &Character,
+        mut view: ReplyView,
+    ) -> Result<Self> {
+        let channel_id = reference.channel_id;
+        let mut webhook_reply = None;
+        if CONFIG.read().use_webhooks() {
+            match Self::send_with_webhook(http, data, channel_id, character, &view).await {
+                Ok(reply) => webhook_reply = reply,
+                Err(why) => {
+                    tracing::debug!("could not use a webhook in {channel_id}, using embeds! {why}");
+                }
+            }
+        }
+        let mut reply = match webhook_reply {
+            Some(reply) => reply,
+            None => {
+                let pages = view.pages(false);
+                let mut message = CreateMessage::default()
+                    .embed(view.embed(character, &pages[0]))
+                    .reference_message(reference);
+                if let Some(components) = view.components.take() {
+                    message = message.components(components);
+                }
+                Self {
+                    message: Box::new(channel_id.send_message(http, message).await?),
+                    webhook: None,
+                    parts: Vec::new(),
+                }
+            }
+        };
+        let pages = view.pages(reply.webhook.is_some());
+        reply
+            .render_parts(http, data, character, &pages[1..])
+            .await?;
+        Ok(reply)
+    }
+
+    async fn send_with_webhook(
+        http: &Http,
+        data: &Data,
+        channel_id: ChannelId,
+        character: &Character,
+        view: &ReplyView,
+    ) -> Result<Option<Self>> {
+        let mut webhook = webhook(http, data, channel_id).await?;
+        let pages = view.pages(true);
+        let mut builder = ExecuteWebhook::new()
+            .username(character.name.to_string())
+            .avatar_url(character.avatar.to_string())
+            .content(view.content(&pages[0]));
+        if let Some(components) = view.components.clone() {
+            builder = builder.components(components);
+        }
+        let message = execute(http, data, channel_id, &mut webhook, builder).await?;
+        Ok(message.map(|message| Self {
+            message: Box::new(message),
+            webhook: Some(Box::new(webhook)),
+            parts: Vec::new(),
+        }))
+    }
+
+    pub async fn from_message(http: &Http, data: &Data, message: Message) -> Result<Self> {
+        let parts = data
+            .reply_parts(message.id)?
+            .into_iter()
+            .map(|part| (part, String::new()))
+            .collect();
+        let mut webhook_for_message = None;
+        if let Some(webhook_id) = message.webhook_id {
+            let webhook = webhook(http, data, message.channel_id).await?;
+            if webhook.id == webhook_id {
+                webhook_for_message = Some(Box::new(webhook));
+            }
+        }
+        Ok(Self {
+            message: Box::new(message),
+            webhook: webhook_for_message,
+            parts,
+        })
+    }
+
+    pub fn id(&self) -> MessageId {
+        self.message.id
+    }
+
+    pub async fn edit(
+        &mut self,
+        http: &Http,
+        data: &Data,
+        character: &Character,
+        view: ReplyView,
+    ) -> Result<()> {
+        let pages = view.pages(self.webhook.is_some());
+        match &self.webhook {
+            None => {
+                let mut edit = EditMessage::default().embed(view.embed(character, &pages[0]));
+                if let Some(components) = view.components {
+                    edit = edit.components(components);
+                }
+                self.message.edit(http, edit).await?;
+            }
+            Some(webhook) => {
+                let mut edit = EditWebhookMessage::new().content(view.content(&pages[0]));
+                if let Some(components) = view.components {
+                    edit = edit.components(components);
+                }
+                *self.message = webhook.edit_message(http, self.message.id, edit).await?;
+            }
+        }
+        self.render_parts(http, data, character, &pages[1..]).await
+    }
+
+    pub async fn set_components(
+        &mut self,
+        http: &Http,
+        components: Vec<CreateActionRow<'static>>,
+    ) -> Result<()> {
+        match &self.webhook {
+            None => {
+                self.message
+                    .edit(http, EditMessage::new().components(components))
+                    .await?;
+            }
+            Some(webhook) => {
+                let edit = EditWebhookMessage::new().components(components);
+                *self.message = webhook.edit_message(http, self.message.id, edit).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn render_parts(
+        &mut self,
+        http: &Http,
+        data: &Data,
+        character: &Character,
+        pages: &[String],
+    ) -> Result<()> {
+        let channel_id = self.message.channel_id;
+        let mut changed = false;
+        for (index, page) in pages.iter().enumerate() {
+            if let Some((part, text)) = self.parts.get_mut(index) {
+                if text == page {
+                    continue;
+                }
+                let edited = match &self.webhook {
+                    Some(webhook) => {
+                        let edit = EditWebhookMessage::new().content(page.clone());
+                        webhook.edit_message(http, *part, edit).await.is_ok()
+                    }
+                    None => false,
+                };
+                // Parts that fell back to embeds are edited like any other embed.
+                if !edited {
+                    let edit =
+                        EditMessage::new().embed(CreateEmbed::new().description(page.clone()));
+                    channel_id.edit_message(http, *part, edit).await?;
+                }
+                page.clone_into(text);
+                continue;
+            }
+            let mut message = None;
+            if let Some(webhook) = &mut self.webhook {
+                let builder = ExecuteWebhook::new()
+                    .username(character.name.to_string())
+                    .avatar_url(character.avatar.to_string())
+                    .content(page.clone());
+                match execute(http, data, channel_id, webhook, builder).await {
+                    Ok(sent) => message = sent,
+                    Err(why) => {
+                        tracing::debug!(
+                            "could not use a webhook in {channel_id}, using embeds! {why}"
+                        );
+                    }
+                }
+            }
+            if message.is_none() {
+                let create = CreateMessage::new()
+                    .embed(CreateEmbed::new().description(page.clone()))
+                    .reference_message(&*self.message);
+                message = Some(channel_id.send_message(http, create).await?);
+            }
+            if let Some(message) = message {
+                self.parts.push((message.id, page.clone()));
+                changed = true;
+            }
+        }
+        let surplus = self.parts.split_off(pages.len().min(self.parts.len()));
+        for (part, _) in surplus {
+            let deleted = match &self.webhook {
+                Some(webhook) => webhook.delete_message(http, None, part).await.is_ok(),
+                None => false,
+            };
+            if !deleted {
+                channel_id.delete_message(http, part, None).await?;
+            }
+            changed = true;
+        }
+        if changed {
+            let parts = self.parts.iter().map(|(part, _)| *part).collect::<Vec<_>>();
+            data.set_reply_parts(self.message.id, &parts)?;
+        }
+        Ok(())
+    }
+}
+
+/// Executes `builder` on `webhook`. A webhook that fails may have been deleted
+/// in Discord, so it is dropped from the cache and a fresh one is tried once.
+async fn execute(
+    http: &Http,
+    data: &Data,
+    channel_id: ChannelId,
+    webhook: &mut Webhook,
+    builder: ExecuteWebhook<'_>,
+) -> Result<Option<Message>> {
+    match webhook.execute(http, true, builder.clone()).await {
+        Ok(message) => Ok(message),
+        Err(why) => {
+            tracing::debug!("webhook in {channel_id} failed, fetching a fresh one! {why}");
+            data.webhooks.remove(&channel_id);
+            *webhook = self::webhook(http, data, channel_id).await?;
+            Ok(webhook.execute(http, true, builder).await?)
+        }
+    }
+}
+
+async fn webhook(http: &Http, data: &Data, channel_id: ChannelId) -> Result<Webhook> {
+    if let Some(webhook) = data.webhooks.get(&channel_id) {
+        return Ok(webhook.clone());
+    }
+    let bot_id = CONFIG.read().bot_id();
+    let existing = channel_id
+        .webhooks(http)
+        .await?
+        .into_iter()
+        .find(|webhook| {
+            webhook.token.is_some() && webhook.user.as_ref().is_some_and(|user| user.id == bot_id)
+        });
+    let webhook = match existing {
+        Some(webhook) => webhook,
+        None => {
+            channel_id
+                .create_webhook(http, CreateWebhook::new(WEBHOOK_NAME))
+                .await?
+        }
+    };
+    data.webhooks.insert(channel_id, webhook.clone());
+    Ok(webhook)
+}
@@ -0,0 +1,218 @@
+use crate::prelude::*;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+pub const DEFAULT_MAX_TOKENS: u16 = 2048;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SamplerSettings {
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub top_k: Option<u32>,
+    #[serde(default)]
+    pub min_p: Option<f32>,
+    #[serde(default)]
+    pub repetition_penalty: Option<f32>,
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u16>,
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub logit_bias: Option<Vec<(String, f32)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum SamplerSetting {
+    #[name = "Förinställning"]
+    Preset,
+    #[name = "Temperatur"]
+    Temperature,
+    #[name = "Top P"]
+    TopP,
+    #[name = "Top K"]
+    TopK,
+    #[name = "Min P"]
+    MinP,
+    #[name = "Upprepningsstraff"]
+    RepetitionPenalty,
+    #[name = "Frekvensstraff"]
+    FrequencyPenalty,
+    #[name = "Närvarostraff"]
+    PresencePenalty,
+    #[name = "Högsta antal tokens"]
+    MaxTokens,
+    #[name = "Stoppsekvenser"]
+    Stop,
+    #[name = "Frö"]
+    Seed,
+    #[name = "Logit bias"]
+    LogitBias,
+}
+
+impl SamplerSetting {
+    /// The setting's name in [`SamplerSettings::describe`].
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Preset => "preset",
+            Self::Temperature => "temperature",
+            Self::TopP => "top_p",
+            Self::TopK => "top_k",
+            Self::MinP => "min_p",
+            Self::RepetitionPenalty => "repetition_penalty",
+            Self::FrequencyPenalty => "frequency_penalty",
+            Self::PresencePenalty => "presence_penalty",
+            Self::MaxTokens => "max_tokens",
+            Self::Stop => "stop",
+            Self::Seed => "seed",
+            Self::LogitBias => "logit_bias",
+        }
+    }
+}
+
+impl SamplerSettings {
+    pub fn clear(&mut self, setting: SamplerSetting) {
+        match setting {
+            SamplerSetting::Preset => self.preset = None,
+            SamplerSetting::Temperature => self.temperature = None,
+            SamplerSetting::TopP => self.top_p = None,
+            SamplerSetting::TopK => self.top_k = None,
+            SamplerSetting::MinP => self.min_p = None,
+            SamplerSetting::RepetitionPenalty => self.repetition_penalty = None,
+            SamplerSetting::FrequencyPenalty => self.frequency_penalty = None,
+            SamplerSetting::PresencePenalty => self.presence_penalty = None,
+            SamplerSetting::MaxTokens => self.max_tokens = None,
+            SamplerSetting::Stop => self.stop = None,
+            SamplerSetting::Seed => self.seed = None,
+            SamplerSetting::LogitBias => self.logit_bias = None,
+        }
+    }
+
+    #[must_use]
+    pub fn or(self, fallback: &Self) -> Self {
+        Self {
+            preset: self.preset.or_else(|| fallback.preset.clone()),
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+            top_k: self.top_k.or(fallback.top_k),
+            min_p: self.min_p.or(fallback.min_p),
+            repetition_penalty: self.repetition_penalty.or(fallback.repetition_penalty),
+            frequency_penalty: self.frequency_penalty.or(fallback.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(fallback.presence_penalty),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            stop: self.stop.or_else(|| fallback.stop.clone()),
+            seed: self.seed.or(fallback.seed),
+            logit_bias: self.logit_bias.or_else(|| fallback.logit_bias.clone()),
+        }
+    }
+
+    pub fn max_tokens(&self) -> u16 {
+        self.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)
+    }
+
+    pub fn stop(&self) -> &[String] {
+        self.stop.as_deref().unwrap_or_default()
+    }
+
+    pub fn describe(&self, locale: &str) -> String {
+        let mut lines = Vec::new();
+        let mut line = |setting: &str, value: Option<String>| {
+            if let Some(value) = value {
+                let name = tr!(locale, "sampler-setting", setting = setting);
+                lines.push(format!("{name}: {value}"));
+            }
+        };
+        line("preset", self.preset.clone());
+        line(
+            "temperature",
+            self.temperature.map(|value| value.to_string()),
+        );
+        line("top_p", self.top_p.map(|value| value.to_string()));
+        line("top_k", self.top_k.map(|value| value.to_string()));
+        line("min_p", self.min_p.map(|value| value.to_string()));
+        line(
+            "repetition_penalty",
+            self.repetition_penalty.map(|value| value.to_string()),
+        );
+        line(
+            "frequency_penalty",
+            self.frequency_penalty.map(|value| value.to_string()),
+        );
+        line(
+            "presence_penalty",
+            self.presence_penalty.map(|value| value.to_string()),
+        );
+        line("max_tokens", self.max_tokens.map(|value| value.to_string()));
+        line(
+            "stop",
+            self.stop.as_ref().map(|stop| {
+                stop.iter()
+                    .map(|sequence| format!("{sequence:?}"))
+                    .join(", ")
+            }),
+        );
+        line("seed", self.seed.map(|value| value.to_string()));
+        line(
+            "logit_bias",
+            self.logit_bias.as_ref().map(|biases| {
+                biases
+                    .iter()
+                    .map(|(token, bias)| format!("{token}:{bias}"))
+                    .join(", ")
+            }),
+        );
+        if lines.is_empty() {
+            tr!(locale, "sampler-no-settings")
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+pub fn resolve(character: &SamplerSettings, chat: &SamplerSettings) -> SamplerSettings {
+    let config = CONFIG.read();
+    let overrides = chat.clone().or(character);
+    let preset = overrides.preset.as_deref().and_then(|name| {
+        let preset = config.sampler_preset(name);
+        if preset.is_none() {
+            warn!("unknown sampler preset {name}, using the defaults");
+        }
+        preset
+    });
+    match preset {
+        Some(preset) => overrides.or(&preset).or(&config.sampler()),
+        None => overrides.or(&config.sampler()),
+    }
+}
+
+pub fn parse_logit_bias(input: &str) -> Option<Vec<(String, f32)>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (token, bias) = pair.rsplit_once(':')?;
+            Some((token.trim().to_string(), bias.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+pub fn parse_stop(input: &str) -> Vec<String> {
+    input
+        .split('|')
+        .map(|sequence| sequence.replace("\\n", "\n"))
+        .filter(|sequence| !sequence.is_empty())
+        .collect()
+}
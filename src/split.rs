@@ -0,0 +1,56 @@
+const SENTENCE_ENDS: [char; 4] = ['.', '!', '?', '…'];
+
+pub fn split_reply(text: &str, limit: usize) -> Vec<String> {
+    let limit = limit.max(1);
+    let mut pages = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > limit {
+        let end = rest
+            .char_indices()
+            .nth(limit)
+            .map_or(rest.len(), |(index, _)| index);
+        let split = boundary(&rest[..end]).unwrap_or(end);
+        let (page, tail) = rest.split_at(split);
+        pages.push(page.trim_end().to_string());
+        rest = tail.trim_start();
+    }
+    if !rest.is_empty() || pages.is_empty() {
+        pages.push(rest.to_string());
+    }
+    pages
+}
+
+/// The whitespace that [`split_reply`] dropped between each pair of `pages`,
+/// so the pages can be joined back into `text` exactly.
+pub fn separators(text: &str, pages: &[String]) -> Vec<String> {
+    let mut rest = text.trim();
+    let mut separators = Vec::new();
+    for page in pages {
+        rest = rest.strip_prefix(page.as_str()).unwrap_or(rest);
+        let tail = rest.trim_start();
+        if separators.len() + 1 < pages.len() {
+            separators.push(rest[..rest.len() - tail.len()].to_string());
+        }
+        rest = tail;
+    }
+    separators
+}
+
+fn boundary(window: &str) -> Option<usize> {
+    let half = window.len() / 2;
+    let paragraph = window.rfind("\n\n").map(|index| index + 2);
+    let sentence = window
+        .char_indices()
+        .filter(|(index, character)| {
+            SENTENCE_ENDS.contains(character)
+                && window[index + character.len_utf8()..].starts_with(char::is_whitespace)
+        })
+        .map(|(index, character)| index + character.len_utf8())
+        .chain(window.rfind('\n').map(|index| index + 1))
+        .max();
+    let whitespace = window.rfind(char::is_whitespace);
+    paragraph
+        .filter(|index| *index > half)
+        .or_else(|| sentence.filter(|index| *index > half))
+        .or_else(|| whitespace.filter(|index| *index > 0))
+}
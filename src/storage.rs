@@ -0,0 +1,460 @@
+use crate::prelude::*;
+
+use crate::{
+    branch::Branch,
+    lorebook::{Lorebook, LorebookRef},
+    macros::CustomMacro,
+    persona::UserPersonas,
+};
+use redb::{Database, Key, ReadableTable, TableDefinition};
+use serde::{de::DeserializeOwned, Serialize};
+use serenity::{ChannelId, GuildId, MessageId, UserId};
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fs::{read, rename},
+    path::Path,
+};
+use tracing::info;
+
+const DATABASE_PATH: &str = "discordtavern.redb";
+/// Keyed by guild, or 0 for shared characters, and name.
+const CHARACTERS: TableDefinition<(u64, &str), &str> = TableDefinition::new("characters");
+/// Pictures from imported PNG cards, by the same key as `CHARACTERS`.
+const AVATARS: TableDefinition<(u64, &str), &[u8]> = TableDefinition::new("avatars");
+const CHATS: TableDefinition<u64, &str> = TableDefinition::new("chats");
+const BRANCHES: TableDefinition<(u64, u64), &str> = TableDefinition::new("chat_branches");
+const REPLY_PARTS: TableDefinition<u64, &str> = TableDefinition::new("reply_parts");
+const REPLY_HEADS: TableDefinition<u64, u64> = TableDefinition::new("reply_heads");
+/// Keyed by guild and name, like `CHARACTERS`.
+const LOREBOOKS: TableDefinition<(u64, &str), &str> = TableDefinition::new("lorebooks");
+const CHANNEL_LOREBOOKS: TableDefinition<u64, &str> = TableDefinition::new("channel_lorebooks");
+const PERSONAS: TableDefinition<u64, &str> = TableDefinition::new("personas");
+const CHANNEL_PROMPT_PRESETS: TableDefinition<u64, &str> =
+    TableDefinition::new("channel_prompt_presets");
+const GUILD_LOCALES: TableDefinition<u64, &str> = TableDefinition::new("guild_locales");
+const CHANNEL_MACROS: TableDefinition<u64, &str> = TableDefinition::new("channel_macros");
+
+pub trait Storage: std::fmt::Debug + Send + Sync {
+    fn characters(&self) -> Result<Vec<Character>>;
+    fn character(
+        &self,
+        character_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Character>>;
+    fn insert_character(&self, character: &Character) -> Result<()>;
+    fn remove_character(
+        &self,
+        character_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Character>>;
+    fn move_character(&self, character: &Character, from: Option<GuildId>) -> Result<bool>;
+    fn avatar(&self, character_name: &str, guild_id: Option<GuildId>) -> Result<Option<Vec<u8>>>;
+    fn set_avatar(&self, character: &Character, png: Option<&[u8]>) -> Result<()>;
+    fn history(&self, message_id: MessageId) -> Result<Option<History>>;
+    fn insert_history(&self, history: &History) -> Result<()>;
+    fn branches(&self, root: MessageId) -> Result<Vec<Branch>>;
+    fn reply_parts(&self, message_id: MessageId) -> Result<Vec<MessageId>>;
+    fn set_reply_parts(&self, message_id: MessageId, parts: &[MessageId]) -> Result<()>;
+    fn reply_head(&self, part: MessageId) -> Result<Option<MessageId>>;
+    fn lorebooks(&self) -> Result<Vec<Lorebook>>;
+    fn lorebook(&self, lorebook_name: &str, guild_id: Option<GuildId>) -> Result<Option<Lorebook>>;
+    fn insert_lorebook(&self, lorebook: &Lorebook) -> Result<()>;
+    fn remove_lorebook(
+        &self,
+        lorebook_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Lorebook>>;
+    fn channel_lorebooks(&self, channel_id: ChannelId) -> Result<Vec<LorebookRef>>;
+    fn set_channel_lorebooks(&self, channel_id: ChannelId, lorebooks: &[LorebookRef])
+        -> Result<()>;
+    fn personas(&self, user_id: UserId) -> Result<UserPersonas>;
+    fn set_personas(&self, user_id: UserId, personas: &UserPersonas) -> Result<()>;
+    fn channel_prompt_preset(&self, channel_id: ChannelId) -> Result<Option<String>>;
+    fn set_channel_prompt_preset(
+        &self,
+        channel_id: ChannelId,
+        preset_name: Option<&str>,
+    ) -> Result<()>;
+    fn guild_locale(&self, guild_id: GuildId) -> Result<Option<String>>;
+    fn set_guild_locale(&self, guild_id: GuildId, locale: Option<&str>) -> Result<()>;
+    fn channel_macros(&self, channel_id: ChannelId) -> Result<Vec<CustomMacro>>;
+    fn set_channel_macros(&self, channel_id: ChannelId, macros: &[CustomMacro]) -> Result<()>;
+}
+
+#[derive(Debug)]
+pub struct RedbStorage {
+    database: Database,
+}
+
+impl RedbStorage {
+    pub fn open() -> Result<Self> {
+        let storage = Self::open_at(DATABASE_PATH)?;
+        storage.migrate_ron_files()?;
+        Ok(storage)
+    }
+
+    pub fn open_at(path: impl AsRef<Path>) -> Result<Self> {
+        let database = Database::create(path)?;
+        let transaction = database.begin_write()?;
+        transaction.open_table(CHARACTERS)?;
+        transaction.open_table(AVATARS)?;
+        transaction.open_table(CHATS)?;
+        transaction.open_table(BRANCHES)?;
+        transaction.open_table(REPLY_PARTS)?;
+        transaction.open_table(REPLY_HEADS)?;
+        transaction.open_table(LOREBOOKS)?;
+        transaction.open_table(CHANNEL_LOREBOOKS)?;
+        transaction.open_table(PERSONAS)?;
+        transaction.open_table(CHANNEL_PROMPT_PRESETS)?;
+        transaction.open_table(GUILD_LOCALES)?;
+        transaction.open_table(CHANNEL_MACROS)?;
+        transaction.commit()?;
+        Ok(Self { database })
+    }
+
+    fn migrate_ron_files(&self) -> Result<()> {
+        let characters = match read("characters.ron") {
+            Ok(bytes) => Some(ron::de::from_bytes::<HashMap<String, Character>>(&bytes)?),
+            Err(_) => None,
+        };
+        let chats = match read("chats.ron") {
+            Ok(bytes) => Some(ron::de::from_bytes::<HashMap<MessageId, History>>(&bytes)?),
+            Err(_) => None,
+        };
+        if characters.is_none() && chats.is_none() {
+            return Ok(());
+        }
+
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(CHARACTERS)?;
+            for (character_name, character) in characters.iter().flatten() {
+                table.insert(
+                    scoped_key(character_name, None),
+                    ron::to_string(character)?.as_str(),
+                )?;
+            }
+            let mut table = transaction.open_table(CHATS)?;
+            let mut branches = transaction.open_table(BRANCHES)?;
+            for (message_id, history) in chats.iter().flatten() {
+                table.insert(message_id.get(), ron::to_string(history)?.as_str())?;
+                let branch = Branch::from(history);
+                branches.insert(
+                    (branch.root.get(), message_id.get()),
+                    ron::to_string(&branch)?.as_str(),
+                )?;
+            }
+        }
+        transaction.commit()?;
+
+        for file_name in ["characters.ron", "chats.ron"] {
+            if Path::new(file_name).exists() {
+                rename(file_name, format!("{file_name}.migrated"))?;
+            }
+        }
+        info!(
+            "migrated {} characters and {} chats from RON files",
+            characters.map_or(0, |characters| characters.len()),
+            chats.map_or(0, |chats| chats.len()),
+        );
+        Ok(())
+    }
+
+    fn all<K: Key + 'static, T: DeserializeOwned>(
+        &self,
+        definition: TableDefinition<K, &str>,
+    ) -> Result<Vec<T>> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(definition)?;
+        let mut values = Vec::new();
+        for entry in table.iter()? {
+            let (_, value) = entry?;
+            values.push(ron::from_str(value.value())?);
+        }
+        Ok(values)
+    }
+
+    fn get<'a, K: Key + 'static, T: DeserializeOwned>(
+        &self,
+        definition: TableDefinition<K, &str>,
+        key: impl Borrow<K::SelfType<'a>>,
+    ) -> Result<Option<T>> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(definition)?;
+        let value = table.get(key)?;
+        Ok(value
+            .map(|value| ron::from_str(value.value()))
+            .transpose()?)
+    }
+
+    fn insert<'a, K: Key + 'static, T: Serialize>(
+        &self,
+        definition: TableDefinition<K, &str>,
+        key: impl Borrow<K::SelfType<'a>>,
+        value: &T,
+    ) -> Result<()> {
+        let serialized = ron::to_string(value)?;
+        let transaction = self.database.begin_write()?;
+        transaction
+            .open_table(definition)?
+            .insert(key, serialized.as_str())?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn remove<'a, K: Key + 'static, T: DeserializeOwned>(
+        &self,
+        definition: TableDefinition<K, &str>,
+        key: impl Borrow<K::SelfType<'a>>,
+    ) -> Result<Option<T>> {
+        let transaction = self.database.begin_write()?;
+        let removed = transaction
+            .open_table(definition)?
+            .remove(key)?
+            .map(|value| ron::from_str(value.value()))
+            .transpose()?;
+        transaction.commit()?;
+        Ok(removed)
+    }
+}
+
+impl Storage for RedbStorage {
+    fn characters(&self) -> Result<Vec<Character>> {
+        self.all(CHARACTERS)
+    }
+
+    fn character(
+        &self,
+        character_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Character>> {
+        self.get(CHARACTERS, scoped_key(character_name, guild_id))
+    }
+
+    fn insert_character(&self, character: &Character) -> Result<()> {
+        let character_name = character.name.to_string();
+        let key = scoped_key(&character_name, character.guild);
+        self.insert(CHARACTERS, key, character)
+    }
+
+    fn remove_character(
+        &self,
+        character_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Character>> {
+        let key = scoped_key(character_name, guild_id);
+        let transaction = self.database.begin_write()?;
+        let removed = transaction
+            .open_table(CHARACTERS)?
+            .remove(key)?
+            .map(|value| ron::from_str(value.value()))
+            .transpose()?;
+        transaction.open_table(AVATARS)?.remove(key)?;
+        transaction.commit()?;
+        Ok(removed)
+    }
+
+    /// Moves a character from the `from` scope to the one recorded on it, in a
+    /// single transaction. Returns `false` without changing anything when the
+    /// target scope already has a character with that name.
+    fn move_character(&self, character: &Character, from: Option<GuildId>) -> Result<bool> {
+        let character_name = character.name.to_string();
+        let key = scoped_key(&character_name, character.guild);
+        let serialized = ron::to_string(character)?;
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(CHARACTERS)?;
+            if table.get(key)?.is_some() {
+                return Ok(false);
+            }
+            table.remove(scoped_key(&character_name, from))?;
+            table.insert(key, serialized.as_str())?;
+            let mut avatars = transaction.open_table(AVATARS)?;
+            let avatar = avatars
+                .remove(scoped_key(&character_name, from))?
+                .map(|png| png.value().to_vec());
+            if let Some(avatar) = avatar {
+                avatars.insert(key, avatar.as_slice())?;
+            }
+        }
+        transaction.commit()?;
+        Ok(true)
+    }
+
+    fn avatar(&self, character_name: &str, guild_id: Option<GuildId>) -> Result<Option<Vec<u8>>> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(AVATARS)?;
+        let png = table.get(scoped_key(character_name, guild_id))?;
+        Ok(png.map(|png| png.value().to_vec()))
+    }
+
+    fn set_avatar(&self, character: &Character, png: Option<&[u8]>) -> Result<()> {
+        let character_name = character.name.to_string();
+        let key = scoped_key(&character_name, character.guild);
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(AVATARS)?;
+            match png {
+                Some(png) => table.insert(key, png)?,
+                None => table.remove(key)?,
+            };
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn history(&self, message_id: MessageId) -> Result<Option<History>> {
+        self.get(CHATS, message_id.get())
+    }
+
+    fn insert_history(&self, history: &History) -> Result<()> {
+        let serialized = ron::to_string(history)?;
+        let branch = Branch::from(history);
+        let transaction = self.database.begin_write()?;
+        transaction
+            .open_table(CHATS)?
+            .insert(history.id.get(), serialized.as_str())?;
+        transaction.open_table(BRANCHES)?.insert(
+            (branch.root.get(), branch.id.get()),
+            ron::to_string(&branch)?.as_str(),
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn branches(&self, root: MessageId) -> Result<Vec<Branch>> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(BRANCHES)?;
+        let mut branches = Vec::new();
+        for entry in table.range((root.get(), 0)..=(root.get(), u64::MAX))? {
+            let (_, value) = entry?;
+            branches.push(ron::from_str(value.value())?);
+        }
+        Ok(branches)
+    }
+
+    fn reply_parts(&self, message_id: MessageId) -> Result<Vec<MessageId>> {
+        Ok(self.get(REPLY_PARTS, message_id.get())?.unwrap_or_default())
+    }
+
+    fn set_reply_parts(&self, message_id: MessageId, parts: &[MessageId]) -> Result<()> {
+        let serialized = ron::to_string(&parts)?;
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(REPLY_PARTS)?;
+            let previous = table
+                .insert(message_id.get(), serialized.as_str())?
+                .map(|value| ron::from_str::<Vec<MessageId>>(value.value()))
+                .transpose()?
+                .unwrap_or_default();
+            let mut heads = transaction.open_table(REPLY_HEADS)?;
+            for part in previous {
+                heads.remove(part.get())?;
+            }
+            for part in parts {
+                heads.insert(part.get(), message_id.get())?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn reply_head(&self, part: MessageId) -> Result<Option<MessageId>> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(REPLY_HEADS)?;
+        let head = table.get(part.get())?;
+        Ok(head.map(|head| MessageId::new(head.value())))
+    }
+
+    fn lorebooks(&self) -> Result<Vec<Lorebook>> {
+        self.all(LOREBOOKS)
+    }
+
+    fn lorebook(&self, lorebook_name: &str, guild_id: Option<GuildId>) -> Result<Option<Lorebook>> {
+        self.get(LOREBOOKS, scoped_key(lorebook_name, guild_id))
+    }
+
+    fn insert_lorebook(&self, lorebook: &Lorebook) -> Result<()> {
+        let key = scoped_key(&lorebook.name, lorebook.guild);
+        self.insert(LOREBOOKS, key, lorebook)
+    }
+
+    fn remove_lorebook(
+        &self,
+        lorebook_name: &str,
+        guild_id: Option<GuildId>,
+    ) -> Result<Option<Lorebook>> {
+        self.remove(LOREBOOKS, scoped_key(lorebook_name, guild_id))
+    }
+
+    fn channel_lorebooks(&self, channel_id: ChannelId) -> Result<Vec<LorebookRef>> {
+        Ok(self
+            .get(CHANNEL_LOREBOOKS, channel_id.get())?
+            .unwrap_or_default())
+    }
+
+    fn set_channel_lorebooks(
+        &self,
+        channel_id: ChannelId,
+        lorebooks: &[LorebookRef],
+    ) -> Result<()> {
+        self.insert(CHANNEL_LOREBOOKS, channel_id.get(), &lorebooks)
+    }
+
+    fn personas(&self, user_id: UserId) -> Result<UserPersonas> {
+        Ok(self.get(PERSONAS, user_id.get())?.unwrap_or_default())
+    }
+
+    fn set_personas(&self, user_id: UserId, personas: &UserPersonas) -> Result<()> {
+        self.insert(PERSONAS, user_id.get(), personas)
+    }
+
+    fn channel_prompt_preset(&self, channel_id: ChannelId) -> Result<Option<String>> {
+        self.get(CHANNEL_PROMPT_PRESETS, channel_id.get())
+    }
+
+    fn set_channel_prompt_preset(
+        &self,
+        channel_id: ChannelId,
+        preset_name: Option<&str>,
+    ) -> Result<()> {
+        preset_name.map_or_else(
+            || {
+                self.remove::<u64, String>(CHANNEL_PROMPT_PRESETS, channel_id.get())
+                    .map(|_| ())
+            },
+            |preset_name| self.insert(CHANNEL_PROMPT_PRESETS, channel_id.get(), &preset_name),
+        )
+    }
+
+    fn guild_locale(&self, guild_id: GuildId) -> Result<Option<String>> {
+        self.get(GUILD_LOCALES, guild_id.get())
+    }
+
+    fn set_guild_locale(&self, guild_id: GuildId, locale: Option<&str>) -> Result<()> {
+        locale.map_or_else(
+            || {
+                self.remove::<u64, String>(GUILD_LOCALES, guild_id.get())
+                    .map(|_| ())
+            },
+            |locale| self.insert(GUILD_LOCALES, guild_id.get(), &locale),
+        )
+    }
+
+    fn channel_macros(&self, channel_id: ChannelId) -> Result<Vec<CustomMacro>> {
+        Ok(self
+            .get(CHANNEL_MACROS, channel_id.get())?
+            .unwrap_or_default())
+    }
+
+    fn set_channel_macros(&self, channel_id: ChannelId, macros: &[CustomMacro]) -> Result<()> {
+        self.insert(CHANNEL_MACROS, channel_id.get(), &macros)
+    }
+}
+
+/// Guild IDs are never 0, so shared characters and lorebooks get a scope of
+/// their own.
+fn scoped_key(name: &str, guild_id: Option<GuildId>) -> (u64, &str) {
+    (guild_id.map_or(0, GuildId::get), name)
+}
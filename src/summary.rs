@@ -0,0 +1,84 @@
+use crate::prelude::*;
+
+use crate::{
+    backend::GenerationRequest, discord::Data, preset::PromptPreset, sampler::SamplerSettings,
+    super_message::Summary,
+};
+use async_openai::types::Role;
+use itertools::Itertools;
+use serenity::ChannelId;
+
+/// Summaries should stick to what happened, so they get a cool sampler of
+/// their own instead of the roleplay settings.
+fn summary_sampler() -> SamplerSettings {
+    SamplerSettings {
+        temperature: Some(0.3),
+        max_tokens: Some(512),
+        ..SamplerSettings::default()
+    }
+}
+
+pub async fn summarise_if_needed(
+    data: &Data,
+    history: &mut History,
+    channel_id: ChannelId,
+) -> Result<()> {
+    let (threshold, turn_count) = {
+        let config = CONFIG.read();
+        (config.summary_threshold(), config.summary_turns())
+    };
+    let turns = history.unsummarised_turns();
+    let tokens = turns
+        .iter()
+        .map(|turn| data.tokenizer.count_message(turn))
+        .sum::<usize>();
+    if turn_count == 0 || tokens <= threshold || turns.len() <= turn_count {
+        return Ok(());
+    }
+
+    let preset = data.prompt_preset_for(history, channel_id)?;
+    let request = create_summary_request(&preset, history.summary.as_ref(), &turns[..turn_count]);
+    let text = data.backend.complete(request).await?.text;
+    let covered_turns = history
+        .summary
+        .as_ref()
+        .map_or(0, |summary| summary.covered_turns)
+        + turn_count;
+    tracing::debug!("summarised {turn_count} turns, {covered_turns} in total");
+    history.summary = Some(Summary {
+        text,
+        covered_turns,
+    });
+    Ok(())
+}
+
+pub fn create_summary_request(
+    preset: &PromptPreset,
+    previous_summary: Option<&Summary>,
+    turns: &[SuperMessage],
+) -> GenerationRequest {
+    let transcript = turns
+        .iter()
+        .map(|turn| {
+            if turn.role == Role::Assistant {
+                format!("{}: {}", turn.author, turn.message)
+            } else {
+                turn.message.clone()
+            }
+        })
+        .join("\n");
+    let transcript = match previous_summary {
+        Some(summary) => format!(
+            "{}\n\n{transcript}",
+            preset
+                .previous_summary
+                .replace("{{summary}}", &summary.text)
+        ),
+        None => transcript,
+    };
+    let messages = vec![
+        SuperMessage::new_system(&preset.summary_prompt),
+        SuperMessage::new_user("User", transcript),
+    ];
+    GenerationRequest::new(messages, "Sammanfattning").sampler(summary_sampler())
+}
@@ -0,0 +1,67 @@
+use crate::cadence::{Cadence, RateLimits};
+use crate::prelude::*;
+use serenity::MessageId;
+use std::time::{Duration, Instant};
+
+const BASE: Duration = Duration::from_secs(1);
+
+#[test]
+fn rate_limits_wait_for_the_reset_and_fast_edits_recover() {
+    let now = Instant::now();
+    let mut cadence = Cadence::new(BASE, now);
+    assert!(!cadence.is_due(now));
+
+    cadence.rate_limited(Duration::from_secs(3), now);
+    assert_eq!(cadence.interval(), Duration::from_secs(3));
+    cadence.rate_limited(Duration::from_secs(2), now);
+    assert_eq!(cadence.interval(), Duration::from_secs(3));
+    cadence.rate_limited(Duration::from_secs(30), now);
+    assert_eq!(cadence.interval(), Duration::from_secs(8));
+
+    for _ in 0..10 {
+        cadence.flushed(now);
+    }
+    assert_eq!(cadence.interval(), BASE);
+}
+
+#[test]
+fn edits_are_due_once_the_interval_has_passed() {
+    let now = Instant::now();
+    let mut cadence = Cadence::new(Duration::from_millis(10), now);
+    cadence.flushed(now);
+    assert!(!cadence.is_due(now + Duration::from_millis(5)));
+    assert!(cadence.is_due(now + Duration::from_millis(10)));
+}
+
+#[test]
+fn rate_limits_are_recorded_by_the_edited_message() {
+    let now = Instant::now();
+    let rate_limits = RateLimits::default();
+    rate_limits.record(
+        "https://discord.com/api/v10/channels/1/messages/42",
+        Duration::from_secs(2),
+        now,
+    );
+    rate_limits.record("/webhooks/7/token/messages/43?wait=true", BASE, now);
+    rate_limits.record("/channels/1/typing", BASE, now);
+
+    assert_eq!(
+        rate_limits.take(MessageId::new(42), now),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(rate_limits.take(MessageId::new(42), now), None);
+    assert_eq!(rate_limits.take(MessageId::new(43), now), Some(BASE));
+}
+
+#[test]
+fn passed_rate_limits_are_forgotten() {
+    let now = Instant::now();
+    let rate_limits = RateLimits::default();
+    rate_limits.record("/channels/1/messages/42", BASE, now);
+    rate_limits.record("/channels/1/messages/43", BASE, now);
+    assert_eq!(rate_limits.take(MessageId::new(42), now + BASE), None);
+
+    rate_limits.record("/channels/1/messages/44", BASE, now + BASE);
+    assert_eq!(rate_limits.take(MessageId::new(43), now), None);
+    assert_eq!(rate_limits.take(MessageId::new(44), now + BASE), Some(BASE));
+}
@@ -0,0 +1,78 @@
+use crate::card::{embed_in_png, CardData, BLANK_PNG};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::Write;
+
+const IEND: &[u8] = b"\x00\x00\x00\x00IEND\xae\x42\x60\x82";
+
+#[test]
+fn cards_are_embedded_before_the_end_of_the_png() {
+    let png = embed_in_png(BLANK_PNG, br#"{"name":"Gubbe"}"#).expect("card is embedded");
+    assert!(png.ends_with(IEND));
+    let card = CardData::from_bytes(&png).expect("card is read back");
+    assert_eq!(card.name, "Gubbe");
+
+    let truncated = &BLANK_PNG[..BLANK_PNG.len() - IEND.len()];
+    let png = embed_in_png(truncated, br#"{"name":"Gubbe"}"#).expect("card is embedded");
+    assert!(png.ends_with(IEND));
+    let card = CardData::from_bytes(&png).expect("card is read back");
+    assert_eq!(card.name, "Gubbe");
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).expect("data is compressed");
+    encoder.finish().expect("data is compressed")
+}
+
+fn with_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (u32::try_from(data.len()).expect("chunk fits"))
+        .to_be_bytes()
+        .to_vec();
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(data);
+    chunk.extend_from_slice(&hasher.finalize().to_be_bytes());
+    let end = BLANK_PNG.len() - IEND.len();
+    [&BLANK_PNG[..end], &chunk, IEND].concat()
+}
+
+#[test]
+fn compressed_and_international_text_chunks_are_read() {
+    let encoded = STANDARD.encode(br#"{"name":"Gubbe"}"#);
+
+    let ztxt = [b"chara\0\0".as_slice(), &compress(encoded.as_bytes())].concat();
+    let card = CardData::from_bytes(&with_chunk(b"zTXt", &ztxt)).expect("zTXt card is read");
+    assert_eq!(card.name, "Gubbe");
+
+    let itxt = [b"chara\0\0\0sv\0\0".as_slice(), encoded.as_bytes()].concat();
+    let card = CardData::from_bytes(&with_chunk(b"iTXt", &itxt)).expect("iTXt card is read");
+    assert_eq!(card.name, "Gubbe");
+
+    let itxt = [
+        b"chara\0\x01\0\0\0".as_slice(),
+        &compress(encoded.as_bytes()),
+    ]
+    .concat();
+    let card =
+        CardData::from_bytes(&with_chunk(b"iTXt", &itxt)).expect("compressed iTXt card is read");
+    assert_eq!(card.name, "Gubbe");
+}
+
+#[test]
+fn imported_sections_are_labelled_in_the_importers_language() {
+    let json = br#"{"name":"Gubbe","description":"En gubbe.","personality":"Glad","scenario":""}"#;
+    let card = CardData::from_json(json).expect("card is read");
+    let imported = card.clone().into_character(None, "en-US");
+    assert_eq!(
+        imported.character.description.message,
+        "En gubbe.\n\nPersonality: Glad"
+    );
+    let imported = card.into_character(None, "sv-SE");
+    assert_eq!(
+        imported.character.description.message,
+        "En gubbe.\n\nPersonlighet: Glad"
+    );
+}
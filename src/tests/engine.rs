@@ -0,0 +1,450 @@
+use super::{
+    database_path, mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING, SECOND_REPLY,
+};
+use crate::prelude::*;
+use crate::{
+    backend::{FinishReason, StreamEvent},
+    branch::leaves,
+    engine,
+    storage::{RedbStorage, Storage},
+};
+use futures::StreamExt;
+use serenity::{MessageId, UserId};
+
+const THIRD_REPLY: MessageId = MessageId::new(40);
+const RESUMED_REPLY: MessageId = MessageId::new(50);
+const REQUESTER: UserId = UserId::new(7);
+
+fn new_history() -> History {
+    Character::new(
+        "Gubbe".into(),
+        Some("Hej, jag är Gubbe!".into()),
+        Some("Gubbe är en glad gubbe.".into()),
+        None,
+        None,
+    )
+    .into_history(GREETING)
+}
+
+fn user_message(message: &str) -> SuperMessage {
+    SuperMessage::new_user("Bob", format!("Bob: {message}"))
+}
+
+#[tokio::test]
+async fn reply_is_streamed_and_persisted() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej", " på", " dig!"])]).await;
+    let mut history = new_history();
+
+    let generation = engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    assert_eq!(generation.text, "Hej på dig!");
+    assert_eq!(generation.finish_reason, Some(FinishReason::Stop));
+    let prompt = &fixture.server.prompts()[0];
+    assert_eq!(prompt[prompt.len() - 2], "Hej, jag är Gubbe!");
+    assert_eq!(prompt[prompt.len() - 1], "Bob: Hej!");
+
+    let stored = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(stored.choices.len(), 1);
+    assert_eq!(stored.choices[0].message, "Hej på dig!");
+    assert_eq!(stored.choices[0].author, "Gubbe");
+    assert_eq!(stored.current_page, 0);
+    assert_eq!(stored.finish_reason(0), Some(FinishReason::Stop));
+}
+
+#[tokio::test]
+async fn stopped_generations_keep_the_partial_reply() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej", " på", " dig!"])]).await;
+    let mut history = new_history();
+
+    engine::start_turn(&mut history, user_message("Hej!"));
+    let stream = engine::stream_reply(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("stream is started");
+    let (generation, mut stream) =
+        engine::track_generation(&fixture.data, FIRST_REPLY, REQUESTER, stream);
+    assert_eq!(
+        engine::generation_requester(&fixture.data, FIRST_REPLY),
+        Some(REQUESTER)
+    );
+    let Some(Ok(StreamEvent::Delta(output))) = stream.next().await else {
+        panic!("the first delta is streamed");
+    };
+    assert!(engine::stop_generation(&fixture.data, FIRST_REPLY));
+    assert!(stream.next().await.is_none());
+    assert!(generation.finish());
+    assert!(!engine::stop_generation(&fixture.data, FIRST_REPLY));
+
+    engine::record_reply(
+        &fixture.data,
+        &mut history,
+        output,
+        FIRST_REPLY,
+        0.5,
+        Some(FinishReason::Cancelled),
+    )
+    .expect("reply is stored");
+    let stored = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(stored.choices[0].message, "Hej");
+    assert_eq!(stored.finish_reason(0), Some(FinishReason::Cancelled));
+}
+
+#[tokio::test]
+async fn dropped_generations_are_forgotten() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej"])]).await;
+    let mut history = new_history();
+
+    engine::start_turn(&mut history, user_message("Hej!"));
+    let stream = engine::stream_reply(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("stream is started");
+    let tracked = engine::track_generation(&fixture.data, FIRST_REPLY, REQUESTER, stream);
+    drop(tracked);
+    assert!(engine::generation_requester(&fixture.data, FIRST_REPLY).is_none());
+    assert!(!engine::stop_generation(&fixture.data, FIRST_REPLY));
+}
+
+#[tokio::test]
+async fn a_message_is_claimed_by_one_button_at_a_time() {
+    let fixture = Fixture::start(Vec::new()).await;
+    let claim = engine::claim_message(&fixture.data, FIRST_REPLY).expect("message is free");
+    assert!(engine::claim_message(&fixture.data, FIRST_REPLY).is_none());
+    assert!(engine::claim_message(&fixture.data, SECOND_REPLY).is_some());
+    drop(claim);
+    assert!(engine::claim_message(&fixture.data, FIRST_REPLY).is_some());
+}
+
+#[tokio::test]
+async fn swiping_regenerates_from_the_same_prompt() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Första"]),
+        MockResponse::Stream(vec!["Andra"]),
+    ])
+    .await;
+    let mut history = new_history();
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    let generation = engine::swipe(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("swipe is generated");
+
+    assert_eq!(generation.text, "Andra");
+    let prompts = fixture.server.prompts();
+    assert_eq!(prompts.len(), 2);
+    assert_eq!(prompts[0], prompts[1]);
+
+    let stored = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(
+        stored
+            .choices
+            .iter()
+            .map(|choice| choice.message.as_str())
+            .collect::<Vec<_>>(),
+        ["Första", "Andra"]
+    );
+    assert_eq!(stored.current_page, 1);
+    assert_eq!(stored.seconds_taken.len(), 2);
+}
+
+#[tokio::test]
+async fn continuing_extends_the_current_choice() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Det var en gång"]),
+        MockResponse::Stream(vec![" en gubbe."]),
+    ])
+    .await;
+    let mut history = new_history();
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Berätta en saga!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    engine::continue_reply(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("continuation is generated");
+
+    let prompts = fixture.server.prompts();
+    assert_eq!(prompts[1][..prompts[1].len() - 1], prompts[0]);
+    assert_eq!(
+        prompts[1].last().map(String::as_str),
+        Some("Det var en gång")
+    );
+    let stored = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(stored.choices.len(), 1);
+    assert_eq!(stored.choices[0].message, "Det var en gång en gubbe.");
+    assert_eq!(stored.finish_reason(0), Some(FinishReason::Stop));
+}
+
+#[tokio::test]
+async fn edits_and_selected_swipes_survive_a_restart() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Första"]),
+        MockResponse::Stream(vec!["Andra"]),
+    ])
+    .await;
+    let mut history = new_history();
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    engine::swipe(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("swipe is generated");
+    engine::edit_swipe(&fixture.data, &mut history, 0, "Redigerad").expect("edit is stored");
+    engine::select_swipe(&fixture.data, &mut history, 0).expect("selection is stored");
+
+    let fixture = fixture.reopen();
+    let stored = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(stored.current_page, 0);
+    assert_eq!(stored.choices[0].message, "Redigerad");
+    assert_eq!(stored.choices[1].message, "Andra");
+}
+
+#[tokio::test]
+async fn next_turn_continues_from_the_selected_swipe() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Första"]),
+        MockResponse::Stream(vec!["Andra"]),
+        MockResponse::Stream(vec!["Tredje"]),
+    ])
+    .await;
+    let mut history = new_history();
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    engine::swipe(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("swipe is generated");
+    engine::select_swipe(&fixture.data, &mut history, 0).expect("selection is stored");
+
+    let mut history = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hur mår du?"),
+        CHANNEL,
+        SECOND_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let prompt = &fixture.server.prompts()[2];
+    assert!(prompt.contains(&"Första".to_string()));
+    assert!(!prompt.contains(&"Andra".to_string()));
+    assert_eq!(prompt.last().map(String::as_str), Some("Bob: Hur mår du?"));
+    assert!(fixture
+        .data
+        .storage
+        .history(SECOND_REPLY)
+        .expect("database can be read")
+        .is_some());
+}
+
+#[tokio::test]
+async fn replying_to_an_older_reply_forks_the_chat() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Första"]),
+        MockResponse::Stream(vec!["Andra"]),
+        MockResponse::Stream(vec!["Tredje"]),
+        MockResponse::Stream(vec!["Fjärde"]),
+        MockResponse::Stream(vec!["Femte"]),
+    ])
+    .await;
+    let stored = |message_id| {
+        fixture
+            .data
+            .storage
+            .history(message_id)
+            .expect("database can be read")
+            .expect("history was stored")
+    };
+    let mut history = new_history();
+    fixture
+        .data
+        .insert_history(history.clone())
+        .expect("greeting is stored");
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    engine::swipe(&fixture.data, &mut history, CHANNEL)
+        .await
+        .expect("swipe is generated");
+    for (message, message_id) in [("Och sen?", SECOND_REPLY), ("Något annat!", THIRD_REPLY)] {
+        engine::reply(
+            &fixture.data,
+            &mut stored(FIRST_REPLY),
+            user_message(message),
+            CHANNEL,
+            message_id,
+        )
+        .await
+        .expect("reply is generated");
+    }
+
+    assert_eq!(stored(FIRST_REPLY).choices.len(), 2);
+    let fork = stored(THIRD_REPLY);
+    assert_eq!(fork.parent, Some(FIRST_REPLY));
+    assert_eq!(fork.root(), GREETING);
+    assert!(!fork
+        .history
+        .iter()
+        .any(|message| message.message == "Bob: Och sen?"));
+    let branches = fixture
+        .data
+        .branches(GREETING)
+        .expect("database can be read");
+    let tips = leaves(&branches)
+        .into_iter()
+        .map(|branch| branch.id)
+        .collect::<Vec<_>>();
+    assert_eq!(tips, [SECOND_REPLY, THIRD_REPLY]);
+
+    engine::resume_branch(&fixture.data, &mut stored(SECOND_REPLY), RESUMED_REPLY)
+        .expect("branch is resumed");
+    engine::reply(
+        &fixture.data,
+        &mut stored(RESUMED_REPLY),
+        user_message("Tillbaka!"),
+        CHANNEL,
+        MessageId::new(60),
+    )
+    .await
+    .expect("reply is generated");
+    let prompt = &fixture.server.prompts()[4];
+    assert!(prompt.contains(&"Bob: Och sen?".to_string()));
+    assert!(!prompt.contains(&"Bob: Något annat!".to_string()));
+    let branches = fixture
+        .data
+        .branches(GREETING)
+        .expect("database can be read");
+    let tips = leaves(&branches)
+        .into_iter()
+        .map(|branch| branch.id)
+        .collect::<Vec<_>>();
+    assert_eq!(tips, [THIRD_REPLY, MessageId::new(60)]);
+}
+
+#[tokio::test]
+async fn backend_errors_are_returned_and_not_persisted() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Status(500),
+        MockResponse::StreamThenError(vec!["Halv"]),
+    ])
+    .await;
+
+    let mut history = new_history();
+    let result = engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let mut history = new_history();
+    let result = engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await;
+    assert!(result.is_err());
+
+    assert!(fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .is_none());
+}
+
+#[test]
+fn reply_parts_lead_back_to_their_head() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    storage
+        .set_reply_parts(FIRST_REPLY, &[SECOND_REPLY, THIRD_REPLY])
+        .expect("parts can be stored");
+    let head = |part| storage.reply_head(part).expect("head can be read");
+    assert_eq!(head(THIRD_REPLY), Some(FIRST_REPLY));
+    assert_eq!(head(FIRST_REPLY), None);
+
+    storage
+        .set_reply_parts(FIRST_REPLY, &[SECOND_REPLY])
+        .expect("parts can be stored");
+    assert_eq!(head(SECOND_REPLY), Some(FIRST_REPLY));
+    assert_eq!(head(THIRD_REPLY), None);
+}
@@ -0,0 +1,178 @@
+use crate::prelude::*;
+use crate::{
+    card::{format_example_blocks, parse_example_blocks},
+    character::{ExampleBlock, ExampleTurn},
+    macros::Macros,
+    preset::{ExampleStrategy, PromptPreset, PromptSection},
+    prompt::Prompt,
+    tokenizer::Tokenizer,
+};
+use serenity::MessageId;
+
+fn gubbe() -> Character {
+    let mut character = Character::new(
+        "Gubbe".into(),
+        Some("Hej!".into()),
+        Some("En glad gubbe.".into()),
+        None,
+        None,
+    );
+    character.set_examples(parse_example_blocks(
+        "<START>\n{{user}}: Hur mår du?\n{{char}}: Bra, {{user}}!\n<START>\n{{char}}: Hallå?",
+    ));
+    character
+}
+
+fn preset(example_strategy: ExampleStrategy) -> PromptPreset {
+    PromptPreset {
+        name: "Test".into(),
+        sections: vec![
+            PromptSection::CharacterDescription,
+            PromptSection::Examples,
+            PromptSection::ChatHistory,
+        ],
+        example_separator: "[Exempel]".into(),
+        example_strategy,
+        ..PromptPreset::default()
+    }
+}
+
+fn history(turns: usize) -> History {
+    let mut history = gubbe().into_history(MessageId::new(10));
+    for index in 1..=turns {
+        history.push_message(SuperMessage::new_user("Bob", format!("Bob: {index}")));
+    }
+    history
+}
+
+fn build(history: &History, example_strategy: ExampleStrategy, budget: usize) -> Prompt {
+    Prompt::build(
+        history,
+        &[],
+        &preset(example_strategy),
+        &Macros::new(history, &[]),
+        &Tokenizer::Heuristic,
+        budget,
+    )
+}
+
+fn has_examples(prompt: &Prompt) -> bool {
+    prompt
+        .messages
+        .iter()
+        .any(|message| message.message == "[Exempel]")
+}
+
+#[test]
+fn blocks_are_parsed_into_turns_and_formatted_back() {
+    let input = "<START>\n{{user}}: Hej!\n{{char}}: Tjena!\nHur är läget?\n{{user}}: Bra.\n<START>\n{{char}}: Hallå?\n";
+    let blocks = parse_example_blocks(input);
+    assert_eq!(
+        blocks,
+        [
+            ExampleBlock {
+                turns: vec![
+                    ExampleTurn {
+                        user: Some("Hej!".into()),
+                        character: Some("Tjena!\nHur är läget?".into()),
+                    },
+                    ExampleTurn {
+                        user: Some("Bra.".into()),
+                        character: None,
+                    },
+                ],
+            },
+            ExampleBlock {
+                turns: vec![ExampleTurn {
+                    user: None,
+                    character: Some("Hallå?".into()),
+                }],
+            },
+        ]
+    );
+    assert_eq!(format_example_blocks(&blocks), input.trim_end());
+    assert_eq!(
+        parse_example_blocks(&format_example_blocks(&blocks)),
+        blocks
+    );
+    assert!(parse_example_blocks("<START>\n<START>").is_empty());
+}
+
+#[test]
+fn examples_are_sent_as_separated_turns_with_macros_filled() {
+    let history = history(1);
+    let prompt = build(&history, ExampleStrategy::Always, usize::MAX);
+    let messages = prompt
+        .messages
+        .iter()
+        .map(|message| message.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "En glad gubbe.",
+            "[Exempel]",
+            "Bob: Hur mår du?",
+            "Bra, Bob!",
+            "[Exempel]",
+            "Hallå?",
+            "Bob: 1",
+        ]
+    );
+}
+
+#[test]
+fn strategies_decide_when_examples_give_way() {
+    let history = history(6);
+    let everything = build(&history, ExampleStrategy::Always, usize::MAX);
+    let without_examples = build(&history, ExampleStrategy::UntilChatLength(0), usize::MAX);
+    assert!(!has_examples(&without_examples));
+    let budget = without_examples.tokens;
+
+    let full = build(&history, ExampleStrategy::UntilContextFull, budget);
+    assert!(!has_examples(&full));
+    assert_eq!(full.dropped_turns, 0);
+    let roomy = build(
+        &history,
+        ExampleStrategy::UntilContextFull,
+        everything.tokens,
+    );
+    assert!(has_examples(&roomy));
+
+    let always = build(&history, ExampleStrategy::Always, budget);
+    assert!(has_examples(&always));
+    assert!(always.dropped_turns > 0);
+
+    assert!(has_examples(&build(
+        &history,
+        ExampleStrategy::UntilChatLength(10),
+        usize::MAX
+    )));
+    assert!(!has_examples(&build(
+        &history,
+        ExampleStrategy::UntilChatLength(5),
+        usize::MAX
+    )));
+}
+
+#[test]
+fn examples_are_not_counted_without_a_place_in_the_preset() {
+    let history = history(1);
+    let mut without_section = preset(ExampleStrategy::Always);
+    without_section
+        .sections
+        .retain(|section| *section != PromptSection::Examples);
+    let prompt = Prompt::build(
+        &history,
+        &[],
+        &without_section,
+        &Macros::new(&history, &[]),
+        &Tokenizer::Heuristic,
+        usize::MAX,
+    );
+    assert!(!has_examples(&prompt));
+    assert_eq!(
+        prompt.tokens,
+        build(&history, ExampleStrategy::UntilChatLength(0), usize::MAX).tokens
+    );
+}
@@ -0,0 +1,213 @@
+use super::{mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING, SECOND_REPLY};
+use crate::prelude::*;
+use crate::{
+    discord::Data,
+    engine,
+    group::{Group, SpeakerStrategy},
+    macros::Macros,
+    preset::PromptPreset,
+    prompt::Prompt,
+    tokenizer::Tokenizer,
+};
+use serenity::MessageId;
+
+fn character(name: &str) -> Character {
+    Character::new(
+        name.into(),
+        Some(format!("Hej, jag är {name}!")),
+        Some(format!("{name} är en glad gubbe.")),
+        None,
+        None,
+    )
+}
+
+fn group_history(strategy: SpeakerStrategy) -> History {
+    Group::new(
+        vec![character("Anna"), character("Bertil"), character("Cecilia")],
+        strategy,
+    )
+    .into_history(GREETING)
+    .expect("group has members")
+}
+
+fn user_message(message: &str) -> SuperMessage {
+    SuperMessage::new_user("Bob", format!("Bob: {message}"))
+}
+
+async fn speakers(data: &Data, history: &mut History, messages: &[&str]) -> Vec<String> {
+    let mut speakers = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        let message_id = MessageId::new(100 + index as u64);
+        engine::reply(data, history, user_message(message), CHANNEL, message_id)
+            .await
+            .expect("reply is generated");
+        speakers.push(history.character.name.to_string());
+    }
+    speakers
+}
+
+#[tokio::test]
+async fn round_robin_skips_muted_members() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej"]); 3]).await;
+    let mut history = group_history(SpeakerStrategy::RoundRobin);
+    history.toggle_group_member("Cecilia");
+
+    let speakers = speakers(
+        &fixture.data,
+        &mut history,
+        &["Hej!", "Hur är läget?", "Bra!"],
+    )
+    .await;
+
+    assert_eq!(speakers, ["Bertil", "Anna", "Bertil"]);
+}
+
+#[tokio::test]
+async fn mentions_choose_the_speaker() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej"]); 3]).await;
+    let mut history = group_history(SpeakerStrategy::Mention);
+
+    let speakers = speakers(
+        &fixture.data,
+        &mut history,
+        &["@cecilia, hur mår du?", "Och sen då?", "@Bertil och @Anna?"],
+    )
+    .await;
+
+    assert_eq!(speakers, ["Cecilia", "Cecilia", "Bertil"]);
+}
+
+#[tokio::test]
+async fn natural_order_asks_the_model_when_nobody_is_mentioned() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Hej"]),
+        MockResponse::Stream(vec!["Cecilia"]),
+        MockResponse::Stream(vec!["Hallå"]),
+    ])
+    .await;
+    let mut history = group_history(SpeakerStrategy::Natural);
+
+    let speakers = speakers(
+        &fixture.data,
+        &mut history,
+        &["Bertil, säg hej!", "Vem är du?"],
+    )
+    .await;
+
+    assert_eq!(speakers, ["Bertil", "Cecilia"]);
+    let prompts = fixture.server.prompts();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[1][1].contains("Anna, Bertil, Cecilia"));
+}
+
+#[tokio::test]
+async fn the_speaker_is_asked_for_in_the_chat_language() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Cecilia"]),
+        MockResponse::Stream(vec!["Hello"]),
+    ])
+    .await;
+    fixture
+        .data
+        .set_channel_prompt_preset(CHANNEL, Some("English"))
+        .expect("channel preset is stored");
+    let mut history = group_history(SpeakerStrategy::Natural);
+
+    let speakers = speakers(&fixture.data, &mut history, &["Hello everyone!"]).await;
+
+    assert_eq!(speakers, ["Cecilia"]);
+    let prompt = &fixture.server.prompts()[0];
+    assert_eq!(prompt[0], PromptPreset::english().speaker_prompt);
+    assert!(prompt[1].starts_with("Characters: Anna, Bertil, Cecilia"));
+    assert!(prompt[1].ends_with("Who replies next?"));
+}
+
+#[tokio::test]
+async fn replies_are_attributed_to_their_speaker() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Jag är Bertil."]),
+        MockResponse::Stream(vec!["Jag är Cecilia."]),
+    ])
+    .await;
+    let mut history = group_history(SpeakerStrategy::RoundRobin);
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    let mut history = fixture
+        .data
+        .storage
+        .history(FIRST_REPLY)
+        .expect("database can be read")
+        .expect("history was stored");
+    assert_eq!(history.character.name.to_string(), "Bertil");
+    assert_eq!(history.choices[0].author, "Bertil");
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        user_message("Och du?"),
+        CHANNEL,
+        SECOND_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+    let prompt = &fixture.server.prompts()[1];
+    for description in [
+        "Anna är en glad gubbe.",
+        "Bertil är en glad gubbe.",
+        "Cecilia är en glad gubbe.",
+    ] {
+        assert!(prompt.contains(&description.to_string()));
+    }
+    assert!(prompt
+        .iter()
+        .any(|message| message.contains("Skriv endast Cecilias nästa svar")));
+    assert!(prompt.contains(&"Jag är Bertil.".to_string()));
+}
+
+#[test]
+fn members_can_join_and_leave_mid_conversation() {
+    let mut history = character("Anna").into_history(GREETING);
+    let turns = history.turns().len();
+    let describes_bertil = |history: &History| {
+        Prompt::build(
+            history,
+            &[],
+            &PromptPreset::svenska(),
+            &Macros::new(history, &[]),
+            &Tokenizer::Heuristic,
+            usize::MAX,
+        )
+        .messages
+        .iter()
+        .any(|message| message.message == "Bertil är en glad gubbe.")
+    };
+
+    assert!(history.add_group_member(character("Bertil")));
+    assert!(!history.add_group_member(character("Bertil")));
+    assert_eq!(history.turns().len(), turns);
+    assert!(describes_bertil(&history));
+
+    assert!(history.remove_group_member("Bertil").is_some());
+    assert!(history.remove_group_member("Anna").is_none());
+    assert!(!describes_bertil(&history));
+}
+
+#[test]
+fn removing_the_current_speaker_passes_the_turn_on() {
+    let mut history = group_history(SpeakerStrategy::RoundRobin);
+    assert_eq!(history.toggle_group_member("Bertil"), Some(true));
+
+    assert!(history.remove_group_member("Anna").is_some());
+    assert_eq!(history.character.name.to_string(), "Cecilia");
+
+    assert!(history.remove_group_member("Cecilia").is_some());
+    assert_eq!(history.character.name.to_string(), "Bertil");
+}
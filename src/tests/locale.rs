@@ -0,0 +1,108 @@
+use super::Fixture;
+use crate::locale::{message_id, pick, TRANSLATIONS};
+use crate::prelude::*;
+use serenity::GuildId;
+use std::collections::BTreeSet;
+
+const GUILD: GuildId = GuildId::new(1);
+
+fn message_ids(catalogue: &str) -> BTreeSet<&str> {
+    catalogue
+        .lines()
+        .filter(|line| line.starts_with(|character: char| character.is_ascii_lowercase()))
+        .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+        .collect()
+}
+
+#[test]
+fn every_catalogue_has_the_same_messages() {
+    let svenska = include_str!("../../locales/sv-SE.ftl");
+    let english = include_str!("../../locales/en-US.ftl");
+
+    assert!(fluent::FluentResource::try_new(svenska.into()).is_ok());
+    assert!(fluent::FluentResource::try_new(english.into()).is_ok());
+    assert_eq!(message_ids(svenska), message_ids(english));
+}
+
+#[test]
+fn unknown_locales_fall_back_to_swedish() {
+    assert_eq!(TRANSLATIONS.resolve(Some("en-GB")), "en-US");
+    assert_eq!(TRANSLATIONS.resolve(Some("de")), "sv-SE");
+    assert_eq!(TRANSLATIONS.resolve(None), "sv-SE");
+}
+
+#[test]
+fn replies_are_formatted_with_arguments() {
+    assert_eq!(
+        tr!("sv-SE", "character-created", character = "Gubbe"),
+        "Hurra! Gubben Gubbe skapades."
+    );
+    assert_eq!(
+        tr!("en-US", "character-created", character = "Gubbe"),
+        "Hooray! The character Gubbe was created."
+    );
+    assert_eq!(
+        tr!(
+            "en-US",
+            "character-imported",
+            character = "Gubbe",
+            unmapped = "",
+            unmapped_count = 0
+        ),
+        "Hooray! The character Gubbe was imported."
+    );
+    assert_eq!(tr!("en-US", "no-such-message"), "no-such-message");
+}
+
+#[test]
+fn reply_footers_follow_the_locale() {
+    let footer = |locale, finish| {
+        tr!(
+            locale,
+            "reply-footer",
+            label = "1/2",
+            seconds = 2.5,
+            characters = 40,
+            tokens = 120,
+            context = 4096,
+            finish = finish,
+        )
+    };
+    assert_eq!(
+        footer("sv-SE", "length"),
+        "1/2 | tog 2.5s | 40 tecken | 120/4096 tokens | avklippt, ⏩ fortsätter"
+    );
+    assert_eq!(
+        footer("en-US", "stop"),
+        "1/2 | took 2.5s | 40 characters | 120/4096 tokens | done"
+    );
+    assert_eq!(
+        footer("en-US", "other"),
+        "1/2 | took 2.5s | 40 characters | 120/4096 tokens"
+    );
+}
+
+#[test]
+fn command_names_are_folded_into_message_ids() {
+    assert_eq!(message_id("lägg_till"), "lagg-till");
+    assert_eq!(message_id("förinställningar"), "forinstallningar");
+    assert_eq!(message_id("Omnämnanden (@Namn)"), "omnamnanden-namn");
+}
+
+#[tokio::test]
+async fn the_server_language_wins_over_the_users() {
+    let fixture = Fixture::start(vec![]).await;
+
+    assert_eq!(pick(&fixture.data, Some(GUILD), Some("en-US")), "en-US");
+    fixture
+        .data
+        .set_guild_locale(GUILD, Some("sv-SE"))
+        .expect("language can be stored");
+    assert_eq!(pick(&fixture.data, Some(GUILD), Some("en-US")), "sv-SE");
+    assert_eq!(pick(&fixture.data, None, Some("en-US")), "en-US");
+    fixture
+        .data
+        .set_guild_locale(GUILD, None)
+        .expect("language can be reset");
+    assert_eq!(pick(&fixture.data, Some(GUILD), Some("en-GB")), "en-US");
+}
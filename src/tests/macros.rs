@@ -0,0 +1,169 @@
+use super::{mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING};
+use crate::prelude::*;
+use crate::{
+    engine,
+    macros::{is_valid_name, roll, CustomMacro, Macros},
+    persona::Persona,
+};
+use chrono::{Local, TimeDelta, TimeZone};
+
+fn history() -> History {
+    Character::new(
+        "Gubbe".into(),
+        Some("Hej {{user}}, jag är {{char}}!".into()),
+        Some("{{char}} är en glad gubbe.".into()),
+        None,
+        None,
+    )
+    .into_history(GREETING)
+}
+
+#[test]
+fn names_dates_and_messages_are_expanded() {
+    let mut history = history();
+    let mut earlier = SuperMessage::new_user("Bob", "Bob: Hej!");
+    let now = Local
+        .with_ymd_and_hms(2024, 5, 17, 14, 30, 0)
+        .single()
+        .expect("valid time");
+    earlier.sent_at = Some((now - TimeDelta::minutes(90)).to_utc());
+    engine::start_turn(&mut history, earlier);
+    engine::start_turn(
+        &mut history,
+        SuperMessage::new_user("Bob", "Bob: Är du där?"),
+    );
+
+    let macros = Macros::new(&history, &[]).at(now);
+
+    assert_eq!(
+        macros.expand("{{char}} och {{user}}, {{date}} {{time}}"),
+        "Gubbe och Bob, 2024-05-17 14:30"
+    );
+    assert_eq!(macros.expand("{{lastMessage}}"), "Är du där?");
+    assert_eq!(macros.expand("{{idle_duration}}"), "1 h");
+    assert_eq!(
+        macros.expand("{{okänt}} {{random}}"),
+        "{{okänt}} {{random}}"
+    );
+}
+
+#[test]
+fn the_active_persona_is_the_user() {
+    let mut history = history();
+    history
+        .personas
+        .push(Persona::new("Alice", "En äventyrare."));
+
+    assert_eq!(Macros::new(&history, &[]).expand("{{user}}"), "Alice");
+}
+
+#[test]
+fn random_pick_and_roll_choose_from_their_arguments() {
+    let history = history();
+    let macros = Macros::new(&history, &[]);
+
+    for _ in 0..20 {
+        assert!(["a", "b", "c"].contains(&macros.expand("{{random:a, b, c}}").as_str()));
+        assert!(["x y", "z"].contains(&macros.expand("{{random::x y::z}}").as_str()));
+        let sum = macros
+            .expand("{{roll:2d6}}")
+            .parse::<i64>()
+            .expect("roll is a number");
+        assert!((2..=12).contains(&sum));
+    }
+    let picked = macros.expand("{{pick:a,b,c,d,e,f}}");
+    for _ in 0..20 {
+        assert_eq!(
+            Macros::new(&history, &[]).expand("{{pick:a,b,c,d,e,f}}"),
+            picked
+        );
+    }
+
+    let mut rng = fastrand::Rng::with_seed(1);
+    assert_eq!(roll("d1+2", &mut rng), Some(3));
+    assert_eq!(roll("3d1 - 1", &mut rng), Some(2));
+    assert_eq!(
+        roll("4", &mut fastrand::Rng::with_seed(2)),
+        roll("1d4", &mut fastrand::Rng::with_seed(2))
+    );
+    assert_eq!(roll("0d6", &mut rng), None);
+    assert_eq!(roll("tärning", &mut rng), None);
+    assert_eq!(roll("d6+9223372036854775807", &mut rng), None);
+}
+
+#[test]
+fn custom_macros_can_use_other_macros() {
+    let history = history();
+    let custom = [
+        CustomMacro {
+            name: "hälsa".into(),
+            text: "Hej {{namn}}!".into(),
+        },
+        CustomMacro {
+            name: "namn".into(),
+            text: "{{user}}".into(),
+        },
+        CustomMacro {
+            name: "loop".into(),
+            text: "{{loop}}".into(),
+        },
+    ];
+    let macros = Macros::new(&history, &custom);
+
+    assert_eq!(macros.expand("{{hälsa}}"), "Hej User!");
+    assert_eq!(macros.expand("{{loop}}"), "{{loop}}");
+    assert!(is_valid_name("hälsa"));
+    assert!(!is_valid_name("lastmessage"));
+    assert!(!is_valid_name("två ord"));
+}
+
+#[test]
+fn macros_that_multiply_stop_expanding() {
+    let history = history();
+    let custom = [CustomMacro {
+        name: "a".into(),
+        text: "{{a}}".repeat(300),
+    }];
+    let macros = Macros::new(&history, &custom);
+
+    let expanded = macros.expand("{{a}} och {{user}}");
+    assert!(expanded.len() < 100_000);
+    assert!(expanded.ends_with(" och {{user}}"));
+    assert_eq!(macros.expand("{{user}}"), "User");
+}
+
+#[tokio::test]
+async fn requests_use_the_expanded_card_and_channel_macros() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej!"])]).await;
+    fixture
+        .data
+        .set_channel_macros(
+            CHANNEL,
+            &[CustomMacro {
+                name: "plats".into(),
+                text: "Stockholm".into(),
+            }],
+        )
+        .expect("macros are stored");
+
+    let mut history = history();
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        SuperMessage::new_user("Bob", "Bob: Var är vi, {{char}}? I {{plats}}?"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let prompt = fixture.server.prompts().remove(0);
+    assert!(prompt.contains(&"Gubbe är en glad gubbe.".to_string()));
+    assert!(prompt.contains(&"Hej Bob, jag är Gubbe!".to_string()));
+    assert!(prompt.contains(&"Bob: Var är vi, Gubbe? I Stockholm?".to_string()));
+    assert!(!prompt.iter().any(|message| message.contains("{{")));
+    assert!(history
+        .turns()
+        .iter()
+        .any(|message| message.message.contains("{{char}}")));
+}
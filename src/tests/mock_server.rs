@@ -0,0 +1,160 @@
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::{collections::VecDeque, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    Stream(Vec<&'static str>),
+    StreamThenError(Vec<&'static str>),
+    Status(u16),
+}
+
+#[derive(Debug)]
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("mock server can bind");
+        let address = listener.local_addr().expect("mock server has an address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let requests = received.clone();
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    if let Err(why) = respond(socket, requests, responses).await {
+                        tracing::warn!("mock server could not respond! {why}");
+                    }
+                });
+            }
+        });
+        Self {
+            url: format!("http://{address}/v1"),
+            requests,
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().clone()
+    }
+
+    pub fn prompts(&self) -> Vec<Vec<String>> {
+        self.requests()
+            .iter()
+            .map(|request| {
+                request["messages"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|message| message["content"].as_str().unwrap_or_default().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+async fn respond(
+    mut socket: TcpStream,
+    requests: Arc<Mutex<Vec<Value>>>,
+    responses: Arc<Mutex<VecDeque<MockResponse>>>,
+) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(index) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|length| length.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    while buffer.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = serde_json::from_slice(&buffer[header_end..]).unwrap_or(Value::Null);
+    requests.lock().push(body);
+
+    let response = responses
+        .lock()
+        .pop_front()
+        .unwrap_or(MockResponse::Status(500));
+    socket.write_all(&render(response)).await?;
+    socket.shutdown().await
+}
+
+fn render(response: MockResponse) -> Vec<u8> {
+    match response {
+        MockResponse::Stream(deltas) => event_stream(&deltas, true),
+        MockResponse::StreamThenError(deltas) => event_stream(&deltas, false),
+        MockResponse::Status(status) => {
+            let body = json!({
+                "error": {
+                    "message": "mock error",
+                    "type": "server_error",
+                    "param": null,
+                    "code": null,
+                }
+            })
+            .to_string();
+            format!(
+                "HTTP/1.1 {status} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .into_bytes()
+        }
+    }
+}
+
+fn event_stream(deltas: &[&str], finish: bool) -> Vec<u8> {
+    let mut body = String::from(
+        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n",
+    );
+    for delta in deltas {
+        body.push_str(&chunk(&json!({ "content": delta }), &Value::Null));
+    }
+    if finish {
+        body.push_str(&chunk(&json!({}), &json!("stop")));
+        body.push_str("data: [DONE]\n\n");
+    } else {
+        body.push_str("data: {\"choices\": \n\n");
+    }
+    body.into_bytes()
+}
+
+fn chunk(delta: &Value, finish_reason: &Value) -> String {
+    let chunk = json!({
+        "id": "mock",
+        "object": "chat.completion.chunk",
+        "created": 0,
+        "model": "mock",
+        "choices": [{
+            "index": 0,
+            "delta": delta,
+            "finish_reason": finish_reason,
+        }],
+    });
+    format!("data: {chunk}\n\n")
+}
@@ -0,0 +1,97 @@
+mod cadence;
+mod card;
+mod engine;
+mod examples;
+mod group;
+mod locale;
+mod macros;
+mod mock_server;
+mod ownership;
+mod persona;
+mod preset;
+mod sampler;
+mod scope;
+mod split;
+
+use crate::prelude::*;
+use crate::{
+    backend::openai::OpenAiBackend, discord::Data, storage::RedbStorage, tokenizer::Tokenizer,
+};
+use dashmap::{DashMap, DashSet};
+use mock_server::{MockResponse, MockServer};
+use serenity::{ChannelId, MessageId};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tempfile::TempDir;
+
+pub const CHANNEL: ChannelId = ChannelId::new(1);
+pub const GREETING: MessageId = MessageId::new(10);
+pub const FIRST_REPLY: MessageId = MessageId::new(20);
+pub const SECOND_REPLY: MessageId = MessageId::new(30);
+
+/// A path for a fresh database, in a directory that is deleted along with
+/// the returned [`TempDir`].
+pub fn database_path() -> (TempDir, PathBuf) {
+    let directory = tempfile::tempdir().expect("temporary directory can be created");
+    let path = directory.path().join("discordtavern.redb");
+    (directory, path)
+}
+
+/// A mock backend and `Data` on a fresh database.
+pub struct Fixture {
+    pub server: MockServer,
+    pub data: Data,
+    path: PathBuf,
+    directory: TempDir,
+}
+
+impl Fixture {
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let server = MockServer::start(responses).await;
+        let (directory, path) = database_path();
+        let data = open_data(&server, &path);
+        Self {
+            server,
+            data,
+            path,
+            directory,
+        }
+    }
+
+    /// Closes the database and opens it again, as a restart would.
+    pub fn reopen(self) -> Self {
+        let Self {
+            server,
+            data,
+            path,
+            directory,
+        } = self;
+        drop(data);
+        let data = open_data(&server, &path);
+        Self {
+            server,
+            data,
+            path,
+            directory,
+        }
+    }
+}
+
+fn open_data(server: &MockServer, path: &Path) -> Data {
+    Data {
+        storage: Box::new(RedbStorage::open_at(path).expect("database can be opened")),
+        backend: Box::new(OpenAiBackend::new(
+            server.url.clone(),
+            "key".into(),
+            "mock".into(),
+        )),
+        tokenizer: Tokenizer::Heuristic,
+        context_size: CONFIG.read().context_size(),
+        webhooks: DashMap::new(),
+        generations: DashMap::new(),
+        busy_messages: DashSet::new(),
+        rate_limits: Arc::default(),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::prelude::*;
+use crate::{lorebook::Lorebook, ownership::Manager};
+use serenity::{GuildId, UserId};
+
+const OWNER: UserId = UserId::new(1);
+const STRANGER: UserId = UserId::new(2);
+const HOME: GuildId = GuildId::new(10);
+const ELSEWHERE: GuildId = GuildId::new(20);
+
+fn manager(user_id: UserId, guild_id: GuildId) -> Manager {
+    Manager {
+        user_id,
+        guild_id: Some(guild_id),
+        guild_admin: false,
+        bot_admin: false,
+    }
+}
+
+fn character() -> Character {
+    Character::new("Gubbe".into(), None, None, None, None).owned_by(OWNER, Some(HOME))
+}
+
+#[test]
+fn only_owners_and_admins_may_manage_characters() {
+    let character = character();
+
+    assert!(manager(OWNER, ELSEWHERE).may_manage(&character));
+    assert!(!manager(STRANGER, HOME).may_manage(&character));
+    assert!(Manager {
+        guild_admin: true,
+        ..manager(STRANGER, HOME)
+    }
+    .may_manage(&character));
+    assert!(!Manager {
+        guild_admin: true,
+        ..manager(STRANGER, ELSEWHERE)
+    }
+    .may_manage(&character));
+    assert!(Manager {
+        bot_admin: true,
+        ..manager(STRANGER, ELSEWHERE)
+    }
+    .may_manage(&character));
+}
+
+#[test]
+fn characters_without_an_owner_are_managed_by_admins() {
+    let shared = Character::new("Gubbe".into(), None, None, None, None);
+    let mut local = shared.clone();
+    local.guild = Some(HOME);
+
+    assert!(!manager(STRANGER, HOME).may_manage(&shared));
+    assert!(!Manager {
+        guild_admin: true,
+        ..manager(STRANGER, HOME)
+    }
+    .may_manage(&shared));
+    assert!(Manager {
+        bot_admin: true,
+        ..manager(STRANGER, ELSEWHERE)
+    }
+    .may_manage(&shared));
+
+    assert!(Manager {
+        guild_admin: true,
+        ..manager(STRANGER, HOME)
+    }
+    .may_manage(&local));
+    assert!(!Manager {
+        guild_admin: true,
+        ..manager(STRANGER, ELSEWHERE)
+    }
+    .may_manage(&local));
+}
+
+#[test]
+fn lorebooks_are_managed_like_characters() {
+    let lorebook = Lorebook::new("Världen").owned_by(OWNER, Some(HOME));
+
+    assert!(manager(OWNER, ELSEWHERE).may_manage(&lorebook));
+    assert!(!manager(STRANGER, HOME).may_manage(&lorebook));
+    assert!(Manager {
+        guild_admin: true,
+        ..manager(STRANGER, HOME)
+    }
+    .may_manage(&lorebook));
+    assert!(!Manager {
+        guild_admin: true,
+        ..manager(STRANGER, HOME)
+    }
+    .may_manage(&Lorebook::new("Världen")));
+}
@@ -0,0 +1,91 @@
+use super::{mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING};
+use crate::prelude::*;
+use crate::{
+    engine,
+    persona::{Persona, UserPersonas},
+};
+use serenity::{ChannelId, UserId};
+
+const OTHER_CHANNEL: ChannelId = ChannelId::new(2);
+const BOB: UserId = UserId::new(1);
+const ALICE: UserId = UserId::new(2);
+
+#[test]
+fn personas_are_chosen_per_channel() {
+    let mut personas = UserPersonas::default();
+    assert!(!personas.insert(Persona::new("Riddare Bob", "En modig riddare.")));
+    assert!(!personas.insert(Persona::new("Bob", "En vanlig kille.")));
+    assert!(personas.insert(Persona::new("bob", "En ovanlig kille.")));
+    assert_eq!(personas.personas.len(), 2);
+
+    assert!(personas.activate(CHANNEL, "riddare bob").is_some());
+    assert!(personas.activate(OTHER_CHANNEL, "Alice").is_none());
+    assert_eq!(
+        personas
+            .active_in(CHANNEL)
+            .map(|persona| persona.name.as_str()),
+        Some("Riddare Bob")
+    );
+    assert!(personas.active_in(OTHER_CHANNEL).is_none());
+
+    assert!(personas.remove("Riddare Bob").is_some());
+    assert!(personas.active_in(CHANNEL).is_none());
+    assert!(personas.deactivate(CHANNEL).is_none());
+}
+
+#[tokio::test]
+async fn persona_descriptions_follow_the_character_description() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej!"])]).await;
+    let mut history = Character::new(
+        "Gubbe".into(),
+        None,
+        Some("Gubbe är en glad gubbe.".into()),
+        None,
+        None,
+    )
+    .into_history(GREETING);
+    history.set_persona(BOB, Persona::new("Bob", "En vanlig kille."));
+    history.set_persona(BOB, Persona::new("Riddare Bob", "En modig riddare."));
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        SuperMessage::new_user("Riddare Bob", "Riddare Bob: Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let prompt = &fixture.server.prompts()[0];
+    let description = prompt
+        .iter()
+        .position(|message| message == "Gubbe är en glad gubbe.")
+        .expect("description is in the prompt");
+    assert_eq!(
+        prompt[description + 1],
+        "[Om användaren Riddare Bob: En modig riddare.]"
+    );
+    assert_eq!(
+        prompt
+            .iter()
+            .filter(|message| message.contains("En modig riddare."))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn chat_personas_belong_to_their_user() {
+    let mut history = Character::new("Gubbe".into(), None, None, None, None).into_history(GREETING);
+    history.set_persona(BOB, Persona::new("bob", "En ovanlig kille."));
+    history.set_persona(ALICE, Persona::new("Bob", "En annan Bob."));
+    history.set_persona(BOB, Persona::new("Riddare Bob", "En modig riddare."));
+
+    let personas = history
+        .personas
+        .iter()
+        .map(|persona| (persona.user_id, persona.name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(personas, [(Some(BOB), "Riddare Bob"), (Some(ALICE), "Bob")]);
+}
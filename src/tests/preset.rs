@@ -0,0 +1,115 @@
+use super::{mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING, SECOND_REPLY};
+use crate::prelude::*;
+use crate::{
+    engine,
+    macros::Macros,
+    preset::{PromptPreset, PromptSection},
+    prompt::Prompt,
+    tokenizer::Tokenizer,
+};
+
+fn gubbe() -> Character {
+    Character::new(
+        "Gubbe".into(),
+        Some("Hej!".into()),
+        Some("{{char}} är en glad gubbe.".into()),
+        None,
+        None,
+    )
+}
+
+#[test]
+fn sections_are_laid_out_in_order_with_macros_filled() {
+    let mut history = gubbe().into_history(GREETING);
+    for index in 1..=3 {
+        history.push_message(SuperMessage::new_user("Bob", format!("Bob: {index}")));
+    }
+    let preset = PromptPreset {
+        name: "Test".into(),
+        sections: vec![
+            PromptSection::MainPrompt("Spela {{char}} för {{user}}.".into()),
+            PromptSection::CharacterDescription,
+            PromptSection::ChatHistory,
+            PromptSection::AuthorsNote {
+                depth: 1,
+                text: "[Håll det kort.]".into(),
+            },
+            PromptSection::PostHistory("Svara som {{char}}.".into()),
+        ],
+        ..PromptPreset::default()
+    };
+
+    let prompt = Prompt::build(
+        &history,
+        &[],
+        &preset,
+        &Macros::new(&history, &[]),
+        &Tokenizer::Heuristic,
+        usize::MAX,
+    );
+    let messages = prompt
+        .messages
+        .iter()
+        .map(|message| message.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Spela Gubbe för Bob.",
+            "Gubbe är en glad gubbe.",
+            "Bob: 1",
+            "Bob: 2",
+            "[Håll det kort.]",
+            "Bob: 3",
+            "Svara som Gubbe.",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn channel_preset_applies_unless_the_character_has_one() {
+    let fixture = Fixture::start(vec![
+        MockResponse::Stream(vec!["Hi!"]),
+        MockResponse::Stream(vec!["Hej!"]),
+    ])
+    .await;
+    fixture
+        .data
+        .set_channel_prompt_preset(CHANNEL, Some("English"))
+        .expect("channel preset is stored");
+
+    let mut history = gubbe().into_history(GREETING);
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        SuperMessage::new_user("Bob", "Bob: Hello!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let mut character = gubbe();
+    character.prompt_preset = Some("Svenska".into());
+    fixture
+        .data
+        .insert_character(character)
+        .expect("character is stored");
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        SuperMessage::new_user("Bob", "Bob: Hej!"),
+        CHANNEL,
+        SECOND_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let prompts = fixture.server.prompts();
+    assert!(prompts[0]
+        .iter()
+        .any(|message| message == "The roleplay begins now."));
+    assert!(prompts[1]
+        .iter()
+        .any(|message| message == "Rollspelet börjas nu."));
+}
@@ -0,0 +1,102 @@
+use super::{mock_server::MockResponse, Fixture, CHANNEL, FIRST_REPLY, GREETING};
+use crate::prelude::*;
+use crate::{
+    engine,
+    preset::PromptPreset,
+    sampler::{parse_logit_bias, resolve, SamplerSetting, SamplerSettings},
+    summary::create_summary_request,
+};
+
+#[test]
+fn chat_settings_override_character_preset_and_defaults() {
+    let character = SamplerSettings {
+        preset: Some("exakt".into()),
+        temperature: Some(0.5),
+        seed: Some(1),
+        ..SamplerSettings::default()
+    };
+    let chat = SamplerSettings {
+        seed: Some(7),
+        ..SamplerSettings::default()
+    };
+
+    let settings = resolve(&character, &chat);
+
+    assert_eq!(settings.seed, Some(7));
+    assert_eq!(settings.temperature, Some(0.5));
+    assert_eq!(settings.top_k, Some(40));
+    assert_eq!(settings.presence_penalty, Some(0.0));
+    assert_eq!(settings.max_tokens(), 2048);
+    assert_eq!(
+        parse_logit_bias("hej: 2, 1234:-100"),
+        Some(vec![("hej".into(), 2.0), ("1234".into(), -100.0)])
+    );
+    assert_eq!(parse_logit_bias("hej"), None);
+}
+
+#[test]
+fn single_settings_can_be_cleared() {
+    let mut settings = SamplerSettings {
+        temperature: Some(0.5),
+        seed: Some(1),
+        ..SamplerSettings::default()
+    };
+
+    settings.clear(SamplerSetting::Seed);
+
+    assert_eq!(
+        settings,
+        SamplerSettings {
+            temperature: Some(0.5),
+            ..SamplerSettings::default()
+        }
+    );
+}
+
+#[test]
+fn summaries_do_not_use_the_roleplay_settings() {
+    let turns = [SuperMessage::new_user("Bob", "Bob: Hej!")];
+
+    let request = create_summary_request(&PromptPreset::svenska(), None, &turns);
+
+    assert_eq!(request.sampler.temperature, Some(0.3));
+    assert_eq!(request.sampler.frequency_penalty, None);
+    assert_eq!(request.sampler.presence_penalty, None);
+    assert_eq!(request.max_tokens, 512);
+}
+
+#[tokio::test]
+async fn request_uses_the_stored_character_and_chat_settings() {
+    let fixture = Fixture::start(vec![MockResponse::Stream(vec!["Hej!"])]).await;
+    let mut character = Character::new("Gubbe".into(), None, None, None, None);
+    let mut history = character.clone().into_history(GREETING);
+    character.sampler = SamplerSettings {
+        temperature: Some(0.75),
+        max_tokens: Some(300),
+        stop: Some(vec!["SLUT".into()]),
+        ..SamplerSettings::default()
+    };
+    fixture
+        .data
+        .storage
+        .insert_character(&character)
+        .expect("character can be stored");
+    history.sampler.seed = Some(42);
+
+    engine::reply(
+        &fixture.data,
+        &mut history,
+        SuperMessage::new_user("Bob", "Bob: Hej!"),
+        CHANNEL,
+        FIRST_REPLY,
+    )
+    .await
+    .expect("reply is generated");
+
+    let request = &fixture.server.requests()[0];
+    assert_eq!(request["temperature"], 0.75);
+    assert_eq!(request["max_tokens"], 300);
+    assert_eq!(request["seed"], 42);
+    assert_eq!(request["stop"], serde_json::json!(["SLUT"]));
+    assert_eq!(request["frequency_penalty"], 0.5);
+}
@@ -0,0 +1,193 @@
+use super::database_path;
+use crate::prelude::*;
+use crate::{
+    character::{is_valid_name, visible_in},
+    lorebook::Lorebook,
+    storage::{RedbStorage, Storage},
+};
+use serenity::{GuildId, UserId};
+
+const OWNER: UserId = UserId::new(1);
+const FIRST_GUILD: GuildId = GuildId::new(10);
+const SECOND_GUILD: GuildId = GuildId::new(20);
+
+fn character(name: &str, description: &str, guild_id: Option<GuildId>) -> Character {
+    let character = Character::new(name.into(), None, Some(description.into()), None, None);
+    match guild_id {
+        Some(guild_id) => character.owned_by(OWNER, Some(guild_id)),
+        None => character,
+    }
+}
+
+#[test]
+fn guilds_keep_their_own_characters_with_the_same_name() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    for character in [
+        character("Bob", "Den första Bob", Some(FIRST_GUILD)),
+        character("Bob", "Den andra Bob", Some(SECOND_GUILD)),
+        character("Bob", "Den offentliga Bob", None),
+        character("Anna", "Den offentliga Anna", None),
+    ] {
+        storage
+            .insert_character(&character)
+            .expect("character can be stored");
+    }
+
+    let first = storage
+        .character("Bob", Some(FIRST_GUILD))
+        .expect("character can be read")
+        .expect("first guild has a Bob");
+    assert_eq!(first.description.to_string(), "Den första Bob");
+    assert!(storage
+        .character("Anna", Some(FIRST_GUILD))
+        .expect("character can be read")
+        .is_none());
+
+    let describe = |guild_id| {
+        let mut descriptions = visible_in(storage.characters().expect("characters"), guild_id)
+            .into_iter()
+            .map(|character| character.description.to_string())
+            .collect::<Vec<_>>();
+        descriptions.sort();
+        descriptions
+    };
+    assert_eq!(
+        describe(Some(SECOND_GUILD)),
+        ["Den andra Bob", "Den offentliga Anna"]
+    );
+    assert_eq!(
+        describe(None),
+        ["Den offentliga Anna", "Den offentliga Bob"]
+    );
+}
+
+#[test]
+fn publishing_refuses_to_replace_a_public_character() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    let public = character("Bob", "Den offentliga Bob", None);
+    storage
+        .insert_character(&public)
+        .expect("character can be stored");
+    let mut local = character("Bob", "Den första Bob", Some(FIRST_GUILD));
+    storage
+        .insert_character(&local)
+        .expect("character can be stored");
+
+    let from = local.guild.take();
+    assert!(!storage
+        .move_character(&local, from)
+        .expect("character can be moved"));
+    assert!(storage
+        .character("Bob", Some(FIRST_GUILD))
+        .expect("character can be read")
+        .is_some());
+
+    storage
+        .remove_character("Bob", None)
+        .expect("character can be removed");
+    assert!(storage
+        .move_character(&local, from)
+        .expect("character can be moved"));
+    assert!(storage
+        .character("Bob", Some(FIRST_GUILD))
+        .expect("character can be read")
+        .is_none());
+    let published = storage
+        .character("Bob", None)
+        .expect("character can be read")
+        .expect("Bob is public");
+    assert_eq!(published.description.to_string(), "Den första Bob");
+}
+
+#[test]
+fn card_pictures_follow_their_character() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    let mut local = character("Bob", "Den första Bob", Some(FIRST_GUILD));
+    storage
+        .insert_character(&local)
+        .expect("character can be stored");
+    storage
+        .set_avatar(&local, Some(b"png"))
+        .expect("avatar can be stored");
+
+    let from = local.guild.take();
+    assert!(storage
+        .move_character(&local, from)
+        .expect("character can be moved"));
+    assert!(storage
+        .avatar("Bob", Some(FIRST_GUILD))
+        .expect("avatar can be read")
+        .is_none());
+    assert_eq!(
+        storage.avatar("Bob", None).expect("avatar can be read"),
+        Some(b"png".to_vec())
+    );
+
+    storage
+        .remove_character("Bob", None)
+        .expect("character can be removed");
+    assert!(storage
+        .avatar("Bob", None)
+        .expect("avatar can be read")
+        .is_none());
+}
+
+#[test]
+fn shared_names_never_meet_server_ones() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    let shared = character(
+        &format!("{}/Bob", FIRST_GUILD.get()),
+        "Den offentliga Bob",
+        None,
+    );
+    storage
+        .insert_character(&shared)
+        .expect("character can be stored");
+    storage
+        .insert_character(&character("Bob", "Den första Bob", Some(FIRST_GUILD)))
+        .expect("character can be stored");
+
+    let local = storage
+        .character("Bob", Some(FIRST_GUILD))
+        .expect("character can be read")
+        .expect("first guild has a Bob");
+    assert_eq!(local.description.to_string(), "Den första Bob");
+    let shared = storage
+        .character(&shared.name.to_string(), None)
+        .expect("character can be read")
+        .expect("the shared character is kept");
+    assert_eq!(shared.description.to_string(), "Den offentliga Bob");
+    assert!(!is_valid_name(&shared.name.to_string()));
+    assert!(is_valid_name("Bob"));
+}
+
+#[test]
+fn guilds_keep_their_own_lorebooks() {
+    let (_directory, path) = database_path();
+    let storage = RedbStorage::open_at(path).expect("database can be opened");
+    for lorebook in [
+        Lorebook::new("Världen").owned_by(OWNER, Some(FIRST_GUILD)),
+        Lorebook::new("Världen").owned_by(OWNER, Some(SECOND_GUILD)),
+        Lorebook::new("Världen"),
+    ] {
+        storage
+            .insert_lorebook(&lorebook)
+            .expect("lorebook can be stored");
+    }
+
+    storage
+        .remove_lorebook("Världen", Some(FIRST_GUILD))
+        .expect("lorebook can be removed");
+    let scopes = |guild_id| {
+        visible_in(storage.lorebooks().expect("lorebooks"), guild_id)
+            .into_iter()
+            .map(|lorebook| lorebook.guild)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(scopes(Some(FIRST_GUILD)), [None]);
+    assert_eq!(scopes(Some(SECOND_GUILD)), [Some(SECOND_GUILD)]);
+}
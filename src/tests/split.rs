@@ -0,0 +1,51 @@
+use crate::split::{separators, split_reply};
+
+#[test]
+fn short_replies_are_left_alone() {
+    assert_eq!(split_reply("Hej på dig!", 20), ["Hej på dig!"]);
+    assert_eq!(split_reply("", 20), [""]);
+}
+
+#[test]
+fn long_replies_split_at_paragraphs_first() {
+    let text = "Första stycket är här.\n\nAndra stycket. Det har två meningar.";
+    assert_eq!(
+        split_reply(text, 40),
+        [
+            "Första stycket är här.",
+            "Andra stycket. Det har två meningar."
+        ]
+    );
+}
+
+#[test]
+fn long_paragraphs_split_at_sentences_then_words() {
+    let text = "En mening som är lång. En till mening som är lång.";
+    assert_eq!(
+        split_reply(text, 30),
+        ["En mening som är lång.", "En till mening som är lång."]
+    );
+    assert_eq!(
+        split_reply("ord ord ord ord ord ord", 10),
+        ["ord ord", "ord ord", "ord ord"]
+    );
+}
+
+#[test]
+fn pages_never_exceed_the_limit() {
+    let text = "å".repeat(25);
+    let pages = split_reply(&text, 10);
+    assert_eq!(pages.len(), 3);
+    assert!(pages.iter().all(|page| page.chars().count() <= 10));
+    assert_eq!(pages.concat(), text);
+}
+
+#[test]
+fn separators_join_the_pages_back_together() {
+    let text = "Första stycket är här.\n\n\nAndra stycket. Det har två meningar.";
+    let pages = split_reply(text, 40);
+    let gaps = separators(text, &pages);
+    assert_eq!(gaps, ["\n\n\n"]);
+    assert_eq!(format!("{}{}{}", pages[0], gaps[0], pages[1]), text);
+    assert!(separators("Hej!", &split_reply("Hej!", 20)).is_empty());
+}
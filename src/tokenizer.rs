@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+use tiktoken_rs::CoreBPE;
+
+const MESSAGE_OVERHEAD: usize = 4;
+const CHARACTERS_PER_TOKEN: usize = 4;
+
+pub enum Tokenizer {
+    Bpe(Box<CoreBPE>),
+    Heuristic,
+}
+
+impl std::fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bpe(_) => f.write_str("Bpe"),
+            Self::Heuristic => f.write_str("Heuristic"),
+        }
+    }
+}
+
+impl Tokenizer {
+    pub fn for_model(model: &str) -> Self {
+        tiktoken_rs::get_bpe_from_model(model)
+            .map_or(Self::Heuristic, |bpe| Self::Bpe(Box::new(bpe)))
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Bpe(bpe) => bpe.encode_ordinary(text).len(),
+            Self::Heuristic => text.chars().count().div_ceil(CHARACTERS_PER_TOKEN),
+        }
+    }
+
+    pub fn count_message(&self, message: &SuperMessage) -> usize {
+        self.count(&message.message) + self.count(&message.author) + MESSAGE_OVERHEAD
+    }
+}